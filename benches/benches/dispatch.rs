@@ -0,0 +1,58 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// Finds the instruction by comparing the discriminator slice against each known discriminator
+/// in turn, which is what the `x if x == Struct::DISCRIMINATOR` match guards used to compile to.
+fn linear_scan(table: &[(&'static str, &[u8])], data: &[u8]) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(_, candidate)| data.starts_with(candidate))
+        .map(|(name, _)| *name)
+}
+
+/// Builds one input per instruction: its discriminator followed by zeroed argument bytes.
+fn inputs(table: &[(&'static str, &[u8])]) -> Vec<Vec<u8>> {
+    table
+        .iter()
+        .map(|(_, disc)| {
+            let mut data = disc.to_vec();
+            data.extend([0u8; 256]);
+            data
+        })
+        .collect()
+}
+
+macro_rules! bench_program {
+    ($c:expr, $name:literal, $decoder:ident) => {{
+        let table = $decoder::INSTRUCTION_DISCRIMINATORS;
+        let inputs = inputs(table);
+
+        let mut group = $c.benchmark_group(concat!("dispatch/", $name));
+        group.bench_function("linear_scan", |b| {
+            b.iter(|| {
+                for data in &inputs {
+                    black_box(linear_scan(table, black_box(data)));
+                }
+            })
+        });
+        group.bench_function("integer_match", |b| {
+            b.iter(|| {
+                for data in &inputs {
+                    black_box($decoder::instruction_name(black_box(data)));
+                }
+            })
+        });
+        group.finish();
+    }};
+}
+
+fn dispatch(c: &mut Criterion) {
+    bench_program!(c, "express-relay", express_relay_decoder);
+    bench_program!(c, "jupiter", jupiter_decoder);
+    bench_program!(c, "pumpdotfun", pumpdotfun_decoder);
+    bench_program!(c, "tensor-amm", tensor_amm_decoder);
+    bench_program!(c, "tensor-marketplace", tensor_marketplace_decoder);
+    bench_program!(c, "whirlpools", whirlpools_decoder);
+}
+
+criterion_group!(benches, dispatch);
+criterion_main!(benches);
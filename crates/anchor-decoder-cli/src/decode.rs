@@ -0,0 +1,263 @@
+use std::fmt;
+
+use anchor_decoder_codegen::{account_names, EMIT_CPI_INSTRUCTION_DISCRIMINATOR};
+use serde_json::{json, Map, Number, Value};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::idl::{item_name, Idl};
+
+/// Error returned when data doesn't match the layout described by the IDL
+#[derive(Debug)]
+pub enum DecodeError {
+    /// Data ended before the value being read
+    UnexpectedEnd,
+    InvalidBool(u8),
+    InvalidOption(u8),
+    InvalidUtf8,
+    InvalidVariant {
+        type_name: String,
+        index: u8,
+    },
+    /// A `defined` type that isn't in the IDL's `types`
+    UnknownType(String),
+    UnsupportedType(Value),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of data"),
+            DecodeError::InvalidBool(b) => write!(f, "invalid bool value {}", b),
+            DecodeError::InvalidOption(b) => write!(f, "invalid option tag {}", b),
+            DecodeError::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+            DecodeError::InvalidVariant { type_name, index } => {
+                write!(
+                    f,
+                    "invalid variant index {} for enum `{}`",
+                    index, type_name
+                )
+            }
+            DecodeError::UnknownType(name) => {
+                write!(f, "type `{}` is not defined in the IDL", name)
+            }
+            DecodeError::UnsupportedType(ty) => write!(f, "unsupported IDL type {}", ty),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Decodes instruction data into `{ kind, name, accounts, args }`. Account keys are named by
+/// position; keys past the IDL's accounts are listed under `remaining_accounts`. Events emitted
+/// through emit_cpi are decoded as events.
+pub fn decode_instruction(
+    idl: &Idl,
+    data: &[u8],
+    accounts: &[Pubkey],
+) -> Result<Option<Value>, DecodeError> {
+    if idl.has_events() {
+        if let Some(event_data) = data.strip_prefix(&EMIT_CPI_INSTRUCTION_DISCRIMINATOR[..]) {
+            return decode_event(idl, event_data);
+        }
+    }
+    let Some((instruction, mut args_data)) = idl.find_instruction(data) else {
+        return Ok(None);
+    };
+
+    let names = account_names(instruction);
+    let mut named = Map::new();
+    for (name, key) in names.iter().zip(accounts) {
+        named.insert(name.clone(), json!(key.to_string()));
+    }
+    let remaining: Vec<Value> = accounts
+        .iter()
+        .skip(names.len())
+        .map(|key| json!(key.to_string()))
+        .collect();
+
+    let args = match instruction.get("args").and_then(|v| v.as_array()) {
+        Some(args) => decode_fields(args, &mut args_data, idl)?,
+        None => Value::Object(Map::new()),
+    };
+
+    let mut decoded = Map::new();
+    decoded.insert("kind".into(), json!("instruction"));
+    decoded.insert("name".into(), json!(item_name(instruction)));
+    if !accounts.is_empty() {
+        decoded.insert("accounts".into(), Value::Object(named));
+    }
+    if !remaining.is_empty() {
+        decoded.insert("remaining_accounts".into(), Value::Array(remaining));
+    }
+    decoded.insert("args".into(), args);
+    Ok(Some(Value::Object(decoded)))
+}
+
+/// Decodes account data into `{ kind, name, data }`
+pub fn decode_account(idl: &Idl, data: &[u8]) -> Result<Option<Value>, DecodeError> {
+    let Some((type_def, mut rest)) = idl.find_account(data) else {
+        return Ok(None);
+    };
+    let value = decode_type_def(type_def, &mut rest, idl)?;
+    Ok(Some(
+        json!({ "kind": "account", "name": item_name(type_def), "data": value }),
+    ))
+}
+
+/// Decodes event data into `{ kind, name, data }`
+pub fn decode_event(idl: &Idl, data: &[u8]) -> Result<Option<Value>, DecodeError> {
+    let Some((type_def, mut rest)) = idl.find_event(data) else {
+        return Ok(None);
+    };
+    let value = decode_type_def(type_def, &mut rest, idl)?;
+    Ok(Some(
+        json!({ "kind": "event", "name": item_name(type_def), "data": value }),
+    ))
+}
+
+/// Decodes a list of fields, either named (`{ "name", "type" }`, decoded to an object) or, for
+/// tuple structs and variants, bare types (decoded to an array)
+fn decode_fields(fields: &[Value], data: &mut &[u8], idl: &Idl) -> Result<Value, DecodeError> {
+    let named = fields.iter().all(|f| f.get("name").is_some());
+    if named {
+        let mut object = Map::new();
+        for field in fields {
+            let ty = field.get("type").unwrap_or(&Value::Null);
+            object.insert(item_name(field).to_string(), decode_type(ty, data, idl)?);
+        }
+        Ok(Value::Object(object))
+    } else {
+        fields
+            .iter()
+            .map(|ty| decode_type(ty, data, idl))
+            .collect::<Result<_, _>>()
+            .map(Value::Array)
+    }
+}
+
+/// Decodes an IDL `types` entry. Enums decode to the variant name when the variant has no
+/// fields, and to `{ "<variant>": fields }` otherwise.
+fn decode_type_def(type_def: &Value, data: &mut &[u8], idl: &Idl) -> Result<Value, DecodeError> {
+    let name = item_name(type_def);
+    let type_info = type_def.get("type").unwrap_or(&Value::Null);
+    match type_info.get("kind").and_then(|v| v.as_str()) {
+        Some("struct") => match type_info.get("fields").and_then(|v| v.as_array()) {
+            Some(fields) => decode_fields(fields, data, idl),
+            None => Ok(Value::Object(Map::new())),
+        },
+        Some("enum") => {
+            let index = take::<1>(data)?[0];
+            let variant = type_info
+                .get("variants")
+                .and_then(|v| v.as_array())
+                .and_then(|variants| variants.get(index as usize))
+                .ok_or_else(|| DecodeError::InvalidVariant {
+                    type_name: name.to_string(),
+                    index,
+                })?;
+            let variant_name = item_name(variant);
+            match variant.get("fields").and_then(|v| v.as_array()) {
+                Some(fields) => Ok(json!({ variant_name: decode_fields(fields, data, idl)? })),
+                None => Ok(json!(variant_name)),
+            }
+        }
+        Some("type") => match type_info.get("alias") {
+            Some(alias) => decode_type(alias, data, idl),
+            None => Err(DecodeError::UnsupportedType(type_info.clone())),
+        },
+        _ => Err(DecodeError::UnsupportedType(type_info.clone())),
+    }
+}
+
+/// Decodes a single IDL type. Integers wider than 64 bits are rendered as strings so they
+/// survive JSON parsers that read numbers as doubles.
+fn decode_type(ty: &Value, data: &mut &[u8], idl: &Idl) -> Result<Value, DecodeError> {
+    if let Some(s) = ty.as_str() {
+        return Ok(match s {
+            "u8" => json!(take::<1>(data)?[0]),
+            "u16" => json!(u16::from_le_bytes(take(data)?)),
+            "u32" => json!(u32::from_le_bytes(take(data)?)),
+            "u64" => json!(u64::from_le_bytes(take(data)?)),
+            "u128" => json!(u128::from_le_bytes(take(data)?).to_string()),
+            "i8" => json!(i8::from_le_bytes(take(data)?)),
+            "i16" => json!(i16::from_le_bytes(take(data)?)),
+            "i32" => json!(i32::from_le_bytes(take(data)?)),
+            "i64" => json!(i64::from_le_bytes(take(data)?)),
+            "i128" => json!(i128::from_le_bytes(take(data)?).to_string()),
+            "f32" => float(f32::from_le_bytes(take(data)?) as f64),
+            "f64" => float(f64::from_le_bytes(take(data)?)),
+            "bool" => match take::<1>(data)?[0] {
+                0 => json!(false),
+                1 => json!(true),
+                b => return Err(DecodeError::InvalidBool(b)),
+            },
+            "pubkey" => json!(Pubkey::new_from_array(take(data)?).to_string()),
+            "string" => {
+                let bytes = take_len_prefixed(data)?;
+                json!(String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidUtf8)?)
+            }
+            "bytes" => json!(take_len_prefixed(data)?),
+            _ => return Err(DecodeError::UnsupportedType(ty.clone())),
+        });
+    }
+
+    if let Some(inner) = ty.get("vec") {
+        let len = u32::from_le_bytes(take(data)?);
+        return (0..len)
+            .map(|_| decode_type(inner, data, idl))
+            .collect::<Result<_, _>>()
+            .map(Value::Array);
+    }
+    if let Some(inner) = ty.get("option") {
+        return match take::<1>(data)?[0] {
+            0 => Ok(Value::Null),
+            1 => decode_type(inner, data, idl),
+            b => Err(DecodeError::InvalidOption(b)),
+        };
+    }
+    if let Some([inner, len]) = ty.get("array").and_then(|v| v.as_array()).map(|v| &v[..]) {
+        let len = len
+            .as_u64()
+            .ok_or_else(|| DecodeError::UnsupportedType(ty.clone()))?;
+        return (0..len)
+            .map(|_| decode_type(inner, data, idl))
+            .collect::<Result<_, _>>()
+            .map(Value::Array);
+    }
+    if let Some(defined) = ty.get("defined") {
+        let name = defined
+            .get("name")
+            .or(Some(defined))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| DecodeError::UnsupportedType(ty.clone()))?;
+        let type_def = idl
+            .type_def(name)
+            .ok_or_else(|| DecodeError::UnknownType(name.to_string()))?;
+        return decode_type_def(type_def, data, idl);
+    }
+
+    Err(DecodeError::UnsupportedType(ty.clone()))
+}
+
+fn float(value: f64) -> Value {
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+fn take<const N: usize>(data: &mut &[u8]) -> Result<[u8; N], DecodeError> {
+    if data.len() < N {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    let (bytes, rest) = data.split_at(N);
+    *data = rest;
+    Ok(bytes.try_into().unwrap())
+}
+
+fn take_len_prefixed<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], DecodeError> {
+    let len = u32::from_le_bytes(take(data)?) as usize;
+    if data.len() < len {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(bytes)
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+
+/// An Anchor IDL loaded at runtime. Like the `anchor_idl` macro, items are read straight from the
+/// JSON rather than through a typed model, so unknown fields and newer IDL additions are ignored.
+pub struct Idl {
+    pub name: String,
+    pub address: Pubkey,
+    instructions: Vec<Value>,
+    accounts: Vec<Value>,
+    events: Vec<Value>,
+    types: HashMap<String, Value>,
+}
+
+impl Idl {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read IDL file at {}: {}", path.display(), e))?;
+        let idl: Value = serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid JSON in IDL file {}: {}", path.display(), e))?;
+        Self::from_json(idl)
+    }
+
+    pub fn from_json(idl: Value) -> Result<Self, String> {
+        let address = idl
+            .get("address")
+            .and_then(|v| v.as_str())
+            .ok_or("IDL missing program address")?;
+        let address = Pubkey::from_str(address)
+            .map_err(|e| format!("Invalid program address `{}`: {}", address, e))?;
+        let name = idl
+            .get("metadata")
+            .and_then(|m| m.get("name"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        let list = |key: &str| {
+            idl.get(key)
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default()
+        };
+        let types = list("types")
+            .into_iter()
+            .filter_map(|t| Some((t.get("name")?.as_str()?.to_string(), t)))
+            .collect();
+
+        Ok(Self {
+            name,
+            address,
+            instructions: list("instructions"),
+            accounts: list("accounts"),
+            events: list("events"),
+            types,
+        })
+    }
+
+    /// Finds the instruction whose discriminator prefixes `data`, returning it along with the
+    /// remaining argument bytes
+    pub fn find_instruction<'a, 'b>(&'a self, data: &'b [u8]) -> Option<(&'a Value, &'b [u8])> {
+        find_by_discriminator(&self.instructions, data)
+    }
+
+    /// Finds the account whose discriminator prefixes `data`, returning its type definition
+    /// along with the remaining account bytes
+    pub fn find_account<'a, 'b>(&'a self, data: &'b [u8]) -> Option<(&'a Value, &'b [u8])> {
+        let (account, rest) = find_by_discriminator(&self.accounts, data)?;
+        Some((self.type_def(item_name(account))?, rest))
+    }
+
+    /// Finds the event whose discriminator prefixes `data`, returning its type definition along
+    /// with the remaining event bytes
+    pub fn find_event<'a, 'b>(&'a self, data: &'b [u8]) -> Option<(&'a Value, &'b [u8])> {
+        let (event, rest) = find_by_discriminator(&self.events, data)?;
+        Some((self.type_def(item_name(event))?, rest))
+    }
+
+    pub fn has_events(&self) -> bool {
+        !self.events.is_empty()
+    }
+
+    pub fn type_def(&self, name: &str) -> Option<&Value> {
+        self.types.get(name)
+    }
+}
+
+pub fn item_name(item: &Value) -> &str {
+    item.get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown")
+}
+
+/// Returns the item with the longest discriminator that prefixes `data`. The macro rejects IDLs
+/// where one discriminator prefixes another, but the CLI accepts them and prefers the most
+/// specific match.
+fn find_by_discriminator<'a, 'b>(
+    items: &'a [Value],
+    data: &'b [u8],
+) -> Option<(&'a Value, &'b [u8])> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let discriminator = item
+                .get("discriminator")?
+                .as_array()?
+                .iter()
+                .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                .collect::<Option<Vec<u8>>>()?;
+            if discriminator.is_empty() || !data.starts_with(&discriminator) {
+                return None;
+            }
+            Some((item, discriminator.len()))
+        })
+        .max_by_key(|(_, len)| *len)
+        .map(|(item, len)| (item, &data[len..]))
+}
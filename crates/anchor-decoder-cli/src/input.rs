@@ -0,0 +1,225 @@
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+use base64::Engine;
+use clap::ValueEnum;
+use serde_json::Value;
+use solana_account_decoder::UiAccount;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedTransaction, EncodedTransactionWithStatusMeta, UiInstruction, UiMessage,
+    UiParsedInstruction,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    Hex,
+    Base58,
+    Base64,
+}
+
+/// An instruction from a transaction, with its account indices resolved to keys
+pub struct TransactionInstruction {
+    pub instruction_index: usize,
+    /// Position within the instruction's inner instructions, or `None` for a top-level
+    /// instruction
+    pub inner_index: Option<usize>,
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+/// Reads input from a file, or from stdin when no path is given (or the path is `-`)
+pub fn read_input(path: Option<&Path>) -> Result<String, String> {
+    match path {
+        Some(path) if path != Path::new("-") => std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read {}: {}", path.display(), e)),
+        _ => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| format!("Unable to read stdin: {}", e))?;
+            Ok(input)
+        }
+    }
+}
+
+pub fn decode_bytes(input: &str, encoding: Encoding) -> Result<Vec<u8>, String> {
+    let input = input.trim();
+    match encoding {
+        Encoding::Hex => hex::decode(input.trim_start_matches("0x"))
+            .map_err(|e| format!("Invalid hex data: {}", e)),
+        Encoding::Base58 => bs58::decode(input)
+            .into_vec()
+            .map_err(|e| format!("Invalid base58 data: {}", e)),
+        Encoding::Base64 => base64::engine::general_purpose::STANDARD
+            .decode(input)
+            .map_err(|e| format!("Invalid base64 data: {}", e)),
+    }
+}
+
+pub fn parse_pubkey(key: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(key.trim()).map_err(|e| format!("Invalid pubkey `{}`: {}", key, e))
+}
+
+pub fn parse_json(input: &str) -> Result<Value, String> {
+    let json: Value = serde_json::from_str(input).map_err(|e| format!("Invalid JSON: {}", e))?;
+    Ok(unwrap_rpc_response(json))
+}
+
+/// Strips the JSON-RPC envelope (`{ "result": { "value": ... } }`) from a copied RPC response,
+/// so both raw responses and their inner objects are accepted
+fn unwrap_rpc_response(mut json: Value) -> Value {
+    if let Some(result) = json.get_mut("result") {
+        json = result.take();
+    }
+    if json.get("context").is_some() {
+        if let Some(value) = json.get_mut("value") {
+            json = value.take();
+        }
+    }
+    json
+}
+
+/// Parses an account as returned by `getAccountInfo`, in `base64` or `base58` encoding
+pub fn parse_account(json: Value) -> Result<Account, String> {
+    let account: UiAccount =
+        serde_json::from_value(json).map_err(|e| format!("Invalid account JSON: {}", e))?;
+    account
+        .decode()
+        .ok_or_else(|| "Unable to decode account data, expected base64 or base58 encoding".into())
+}
+
+/// Parses a transaction as returned by `getTransaction`, in any encoding, and flattens its
+/// top-level and inner instructions. Loaded addresses from the metadata are appended to the
+/// message's static keys so lookup table accounts resolve.
+pub fn parse_transaction(json: Value) -> Result<Vec<TransactionInstruction>, String> {
+    let tx: EncodedTransactionWithStatusMeta =
+        serde_json::from_value(json).map_err(|e| format!("Invalid transaction JSON: {}", e))?;
+
+    let mut account_keys = Vec::new();
+    let mut instructions = Vec::new();
+    match &tx.transaction {
+        EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
+            UiMessage::Raw(message) => {
+                for key in &message.account_keys {
+                    account_keys.push(parse_pubkey(key)?);
+                }
+                instructions = message
+                    .instructions
+                    .iter()
+                    .cloned()
+                    .map(UiInstruction::Compiled)
+                    .collect();
+            }
+            UiMessage::Parsed(message) => {
+                for account in &message.account_keys {
+                    account_keys.push(parse_pubkey(&account.pubkey)?);
+                }
+                instructions = message.instructions.clone();
+            }
+        },
+        encoded => {
+            let versioned = encoded
+                .decode()
+                .ok_or("Unable to decode transaction, unsupported encoding")?;
+            account_keys.extend_from_slice(versioned.message.static_account_keys());
+            for ix in versioned.message.instructions() {
+                instructions.push(UiInstruction::Compiled(
+                    solana_transaction_status::UiCompiledInstruction {
+                        program_id_index: ix.program_id_index,
+                        accounts: ix.accounts.clone(),
+                        data: bs58::encode(&ix.data).into_string(),
+                        stack_height: None,
+                    },
+                ));
+            }
+        }
+    }
+
+    let mut inner_instructions = Vec::new();
+    if let Some(meta) = &tx.meta {
+        // jsonParsed messages already list loaded addresses among their account keys.
+        let parsed = matches!(
+            &tx.transaction,
+            EncodedTransaction::Json(ui_tx) if matches!(ui_tx.message, UiMessage::Parsed(_))
+        );
+        if let (OptionSerializer::Some(loaded), false) = (&meta.loaded_addresses, parsed) {
+            for key in loaded.writable.iter().chain(&loaded.readonly) {
+                account_keys.push(parse_pubkey(key)?);
+            }
+        }
+        if let OptionSerializer::Some(inner) = &meta.inner_instructions {
+            inner_instructions = inner.clone();
+        }
+    }
+
+    let mut resolved = Vec::new();
+    for (instruction_index, ix) in instructions.iter().enumerate() {
+        resolved.push(resolve_instruction(
+            ix,
+            &account_keys,
+            instruction_index,
+            None,
+        )?);
+        for inner in inner_instructions
+            .iter()
+            .filter(|inner| inner.index as usize == instruction_index)
+        {
+            for (inner_index, ix) in inner.instructions.iter().enumerate() {
+                resolved.push(resolve_instruction(
+                    ix,
+                    &account_keys,
+                    instruction_index,
+                    Some(inner_index),
+                )?);
+            }
+        }
+    }
+    Ok(resolved.into_iter().flatten().collect())
+}
+
+fn resolve_instruction(
+    ix: &UiInstruction,
+    account_keys: &[Pubkey],
+    instruction_index: usize,
+    inner_index: Option<usize>,
+) -> Result<Option<TransactionInstruction>, String> {
+    let key = |index: u8| {
+        account_keys
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| format!("Account index {} out of range", index))
+    };
+    let (program_id, accounts, data) = match ix {
+        UiInstruction::Compiled(ix) => (
+            key(ix.program_id_index)?,
+            ix.accounts
+                .iter()
+                .map(|i| key(*i))
+                .collect::<Result<_, _>>()?,
+            &ix.data,
+        ),
+        UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(ix)) => (
+            parse_pubkey(&ix.program_id)?,
+            ix.accounts
+                .iter()
+                .map(|k| parse_pubkey(k))
+                .collect::<Result<_, _>>()?,
+            &ix.data,
+        ),
+        // Fully parsed instructions belong to programs the RPC node knows how to decode
+        // (system, token, ...), which aren't Anchor programs described by an IDL.
+        UiInstruction::Parsed(UiParsedInstruction::Parsed(_)) => return Ok(None),
+    };
+    Ok(Some(TransactionInstruction {
+        instruction_index,
+        inner_index,
+        program_id,
+        accounts,
+        data: decode_bytes(data, Encoding::Base58)?,
+    }))
+}
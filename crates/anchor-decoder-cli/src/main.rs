@@ -0,0 +1,271 @@
+//! `anchor-decoder` command-line tool. Decodes instruction, account and event data, or every
+//! instruction in a transaction, using an Anchor IDL loaded at runtime.
+//!
+//! ```sh
+//! anchor-decoder instruction --idl idl.json --accounts <KEY>,<KEY> <DATA>
+//! anchor-decoder account --idl idl.json --file account.json
+//! anchor-decoder transaction --idl idl.json tx.json --format json
+//! anchor-decoder diff old_idl.json new_idl.json
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand};
+use serde_json::{json, Map, Value};
+
+mod decode;
+mod idl;
+mod input;
+mod output;
+
+use decode::{decode_account, decode_event, decode_instruction};
+use idl::{item_name, Idl};
+use input::{
+    decode_bytes, parse_account, parse_json, parse_pubkey, parse_transaction, read_input, Encoding,
+};
+use output::{render, render_changes, Format};
+
+/// Decode Anchor program instructions, accounts and events with an IDL loaded at runtime
+#[derive(Parser)]
+#[command(name = "anchor-decoder", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Decode instruction data, naming accounts if their keys are given
+    Instruction {
+        #[command(flatten)]
+        common: CommonArgs,
+        #[command(flatten)]
+        data: DataArgs,
+        /// Encoding of the data
+        #[arg(long, value_enum, default_value_t = Encoding::Base58)]
+        encoding: Encoding,
+        /// Account keys passed to the instruction, in order
+        #[arg(long, value_delimiter = ',')]
+        accounts: Vec<String>,
+    },
+    /// Decode account data, or an account as returned by `getAccountInfo`
+    Account {
+        #[command(flatten)]
+        common: CommonArgs,
+        #[command(flatten)]
+        data: DataArgs,
+        /// Encoding of the data, when it isn't JSON
+        #[arg(long, value_enum, default_value_t = Encoding::Base64)]
+        encoding: Encoding,
+    },
+    /// Decode event data, such as a `Program data:` log line
+    Event {
+        #[command(flatten)]
+        common: CommonArgs,
+        #[command(flatten)]
+        data: DataArgs,
+        /// Encoding of the data
+        #[arg(long, value_enum, default_value_t = Encoding::Base64)]
+        encoding: Encoding,
+    },
+    /// Decode every instruction for the program in a transaction as returned by `getTransaction`
+    Transaction {
+        #[command(flatten)]
+        common: CommonArgs,
+        /// Transaction JSON file, read from stdin when omitted
+        file: Option<PathBuf>,
+    },
+    /// Compare two versions of an IDL, classifying each change as layout-breaking,
+    /// account-index-breaking, account-constraint-breaking or additive. Exits with an error
+    /// status if any change is layout-breaking.
+    Diff {
+        /// The IDL currently in use
+        old: PathBuf,
+        /// The IDL to upgrade to
+        new: PathBuf,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+}
+
+#[derive(Args)]
+struct CommonArgs {
+    /// Path to the program's Anchor IDL
+    #[arg(long)]
+    idl: PathBuf,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(Args)]
+struct DataArgs {
+    /// Encoded data. Read from --file or stdin when omitted
+    data: Option<String>,
+    /// Read the data from a file instead
+    #[arg(long, short, conflicts_with = "data")]
+    file: Option<PathBuf>,
+}
+
+impl DataArgs {
+    fn read(&self) -> Result<String, String> {
+        match &self.data {
+            Some(data) => Ok(data.clone()),
+            None => read_input(self.file.as_deref()),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok((output, code)) => {
+            println!("{}", output);
+            code
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs a command, returning its output and the exit code to report it with
+fn run(cli: Cli) -> Result<(String, ExitCode), String> {
+    match cli.command {
+        Command::Instruction {
+            common,
+            data,
+            encoding,
+            accounts,
+        } => {
+            let idl = Idl::load(&common.idl)?;
+            let data = decode_bytes(&data.read()?, encoding)?;
+            let accounts = accounts
+                .iter()
+                .map(|key| parse_pubkey(key))
+                .collect::<Result<Vec<_>, _>>()?;
+            let decoded = decode_instruction(&idl, &data, &accounts)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| no_match("instruction", &data))?;
+            Ok((render(&[decoded], common.format), ExitCode::SUCCESS))
+        }
+        Command::Account {
+            common,
+            data,
+            encoding,
+        } => {
+            let idl = Idl::load(&common.idl)?;
+            let input = data.read()?;
+            let data = if input.trim_start().starts_with('{') {
+                let account = parse_account(parse_json(&input)?)?;
+                if account.owner != idl.address {
+                    eprintln!(
+                        "warning: account is owned by {}, not {} ({})",
+                        account.owner, idl.name, idl.address
+                    );
+                }
+                account.data
+            } else {
+                decode_bytes(&input, encoding)?
+            };
+            let decoded = decode_account(&idl, &data)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| no_match("account", &data))?;
+            Ok((render(&[decoded], common.format), ExitCode::SUCCESS))
+        }
+        Command::Event {
+            common,
+            data,
+            encoding,
+        } => {
+            let idl = Idl::load(&common.idl)?;
+            let input = data.read()?;
+            let input = input.trim();
+            let data = decode_bytes(
+                input.strip_prefix("Program data:").unwrap_or(input),
+                encoding,
+            )?;
+            let decoded = decode_event(&idl, &data)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| no_match("event", &data))?;
+            Ok((render(&[decoded], common.format), ExitCode::SUCCESS))
+        }
+        Command::Transaction { common, file } => {
+            let idl = Idl::load(&common.idl)?;
+            let instructions = parse_transaction(parse_json(&read_input(file.as_deref())?)?)?;
+
+            let mut items = Vec::new();
+            for ix in instructions
+                .iter()
+                .filter(|ix| ix.program_id == idl.address)
+            {
+                let location = match ix.inner_index {
+                    Some(inner_index) => format!("{}.{}", ix.instruction_index, inner_index),
+                    None => ix.instruction_index.to_string(),
+                };
+                let mut item = Map::new();
+                item.insert("location".into(), json!(location));
+                // A single bad instruction shouldn't hide the rest of the transaction, so failures
+                // are reported inline.
+                match decode_instruction(&idl, &ix.data, &ix.accounts) {
+                    Ok(Some(Value::Object(decoded))) => item.extend(decoded),
+                    Ok(_) => {
+                        item.insert("kind".into(), json!("unknown"));
+                        item.insert("name".into(), json!(hex::encode(discriminator(&ix.data))));
+                    }
+                    Err(e) => {
+                        let name = match idl.find_instruction(&ix.data) {
+                            Some((instruction, _)) => item_name(instruction).to_string(),
+                            None => hex::encode(discriminator(&ix.data)),
+                        };
+                        item.insert("kind".into(), json!("error"));
+                        item.insert("name".into(), json!(name));
+                        item.insert("error".into(), json!(e.to_string()));
+                    }
+                }
+                items.push(Value::Object(item));
+            }
+            if items.is_empty() {
+                return Err(format!(
+                    "Transaction has no instructions for {} ({})",
+                    idl.name, idl.address
+                ));
+            }
+            Ok((render(&items, common.format), ExitCode::SUCCESS))
+        }
+        Command::Diff { old, new, format } => {
+            let changes = anchor_decoder_codegen::diff_idls(&load_json(&old)?, &load_json(&new)?);
+            // Fail on layout-breaking changes, so CI can gate IDL upgrades on the exit code.
+            let code = if changes
+                .iter()
+                .any(|change| change.impact == anchor_decoder_codegen::Impact::LayoutBreaking)
+            {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            };
+            Ok((render_changes(&changes, format), code))
+        }
+    }
+}
+
+fn load_json(path: &Path) -> Result<Value, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read IDL file at {}: {}", path.display(), e))?;
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Invalid JSON in IDL file {}: {}", path.display(), e))
+}
+
+fn discriminator(data: &[u8]) -> &[u8] {
+    &data[..data.len().min(8)]
+}
+
+fn no_match(kind: &str, data: &[u8]) -> String {
+    format!(
+        "No {} in the IDL matches discriminator {}",
+        kind,
+        hex::encode(discriminator(data))
+    )
+}
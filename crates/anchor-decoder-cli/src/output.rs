@@ -0,0 +1,108 @@
+use std::fmt::Write;
+
+use anchor_decoder_codegen::{IdlChange, Impact};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+/// Renders decoded items, pretty-printed as JSON (an array when there is more than one item) or
+/// as indented text
+pub fn render(items: &[Value], format: Format) -> String {
+    match format {
+        Format::Json => {
+            let value = match items {
+                [item] => item.clone(),
+                items => Value::Array(items.to_vec()),
+            };
+            serde_json::to_string_pretty(&value).expect("JSON values always serialize")
+        }
+        Format::Text => items
+            .iter()
+            .map(|item| render_text(item).trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+    }
+}
+
+/// Renders a decoded item as `<kind>: <name>` followed by its fields, one per line
+fn render_text(item: &Value) -> String {
+    let mut out = String::new();
+    let field = |key: &str| item.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+    if let Some(location) = item.get("location").and_then(|v| v.as_str()) {
+        write!(out, "#{} ", location).unwrap();
+    }
+    writeln!(out, "{}: {}", field("kind"), field("name")).unwrap();
+
+    if let Some(object) = item.as_object() {
+        for (key, value) in object {
+            if !matches!(key.as_str(), "kind" | "name" | "location") {
+                write_value(&mut out, key, value, 1);
+            }
+        }
+    }
+    out
+}
+
+fn write_value(out: &mut String, key: &str, value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            writeln!(out, "{}{}:", indent, key).unwrap();
+            for (key, value) in object {
+                write_value(out, key, value, depth + 1);
+            }
+        }
+        // Arrays of scalars (byte arrays, mostly) stay on one line.
+        Value::Array(items) if items.iter().any(|v| v.is_object() || v.is_array()) => {
+            writeln!(out, "{}{}:", indent, key).unwrap();
+            for (i, value) in items.iter().enumerate() {
+                write_value(out, &format!("[{}]", i), value, depth + 1);
+            }
+        }
+        Value::String(s) => writeln!(out, "{}{}: {}", indent, key, s).unwrap(),
+        value => writeln!(out, "{}{}: {}", indent, key, value).unwrap(),
+    }
+}
+
+/// Renders IDL changes as a JSON array, or as text grouped by impact, most severe first
+pub fn render_changes(changes: &[IdlChange], format: Format) -> String {
+    match format {
+        Format::Json => {
+            let changes: Vec<Value> = changes
+                .iter()
+                .map(|change| {
+                    json!({
+                        "impact": change.impact.to_string(),
+                        "item": change.item,
+                        "description": change.description,
+                    })
+                })
+                .collect();
+            serde_json::to_string_pretty(&changes).expect("JSON values always serialize")
+        }
+        Format::Text if changes.is_empty() => "No changes".to_string(),
+        Format::Text => {
+            let mut groups = Vec::new();
+            for impact in [
+                Impact::LayoutBreaking,
+                Impact::AccountIndexBreaking,
+                Impact::AccountConstraintBreaking,
+                Impact::Additive,
+            ] {
+                let mut out = String::new();
+                for change in changes.iter().filter(|change| change.impact == impact) {
+                    writeln!(out, "  {}: {}", change.item, change.description).unwrap();
+                }
+                if !out.is_empty() {
+                    groups.push(format!("{}:\n{}", impact, out.trim_end()));
+                }
+            }
+            groups.join("\n\n")
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::HashSet;
+
+use quote::quote;
+use serde_json::Value;
+
+use crate::docs::doc_attrs;
+use crate::idl::list;
+use crate::pda::{InstructionPdas, PdaDerivation, Source};
+
+/// Generates a `<Instruction>Keys` struct holding the accounts of an instruction that can't be
+/// derived, and an `instruction` method on the instruction's struct that builds a
+/// `solana_sdk::instruction::Instruction` from its args and those keys.
+///
+/// Accounts with a fixed `address` are filled in, and PDA accounts are derived from their seeds
+/// (in dependency order, since seeds may reference other PDAs). PDAs that can't be derived from
+/// the keys and args alone, such as those seeded by a field of another account's data, are
+/// left in the keys struct, and their docs say which seed is missing. Returns `None` for
+/// instructions with composite account groups.
+pub fn instruction_builder(
+    instruction: &Value,
+    struct_name: &syn::Ident,
+    keys_struct: &syn::Ident,
+    accounts_struct: &syn::Ident,
+    pdas: &InstructionPdas,
+) -> Option<proc_macro2::TokenStream> {
+    let accounts = instruction.get("accounts")?.as_array()?;
+    if accounts
+        .iter()
+        .any(|account| account.get("accounts").is_some())
+    {
+        return None;
+    }
+    let account_name = |account: &Value| {
+        account
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let optional: HashSet<String> = accounts
+        .iter()
+        .filter(|account| flag(account, &["optional", "isOptional"]))
+        .map(account_name)
+        .collect();
+
+    // Work out which PDAs can be derived, and in what order. A PDA can be derived once every
+    // account seeding it is known; optional accounts and fields of account data aren't.
+    let fixed: HashSet<String> = accounts
+        .iter()
+        .filter(|account| account.get("address").is_some_and(|a| a.is_string()))
+        .map(account_name)
+        .collect();
+    let mut pending: Vec<&PdaDerivation> = pdas
+        .derivations
+        .iter()
+        .filter(|pda| !fixed.contains(&pda.account) && !optional.contains(&pda.account))
+        .filter(|pda| {
+            pda.params.iter().all(|(source, _)| match source {
+                Source::Account(account) => !optional.contains(account),
+                Source::AccountField { .. } => false,
+                Source::Arg(_) => true,
+            })
+        })
+        .collect();
+    let mut derived: Vec<&PdaDerivation> = Vec::new();
+    while let Some(index) = pending.iter().position(|pda| {
+        pda.params.iter().all(|(source, _)| match source {
+            Source::Account(account) => !pending.iter().any(|other| other.account == *account),
+            _ => true,
+        })
+    }) {
+        derived.push(pending.remove(index));
+    }
+    // PDAs left pending depend on each other; the caller has to supply them.
+    let derived_names: HashSet<&str> = derived.iter().map(|pda| pda.account.as_str()).collect();
+    // Locals are prefixed so accounts can't shadow `keys`, `data` or each other's bindings.
+    let local =
+        |name: &str| syn::Ident::new(&format!("__acct_{}", name), proc_macro2::Span::call_site());
+
+    let mut key_fields = Vec::new();
+    let mut locals = Vec::new();
+    for account in accounts {
+        let name = account_name(account);
+        let ident = syn::Ident::new(&name, proc_macro2::Span::call_site());
+        let local = local(&name);
+        if let Some(address) = account.get("address").and_then(|v| v.as_str()) {
+            locals.push(quote! { let #local = ::solana_sdk::pubkey!(#address); });
+        } else if !derived_names.contains(name.as_str()) {
+            let docs = doc_attrs(account);
+            let ty = if optional.contains(&name) {
+                quote! { Option<::solana_sdk::pubkey::Pubkey> }
+            } else {
+                quote! { ::solana_sdk::pubkey::Pubkey }
+            };
+            // Say why a PDA has to be passed in, naming the seed that's missing, and how to
+            // derive it.
+            let pda_doc = account
+                .get("pda")
+                .map(|pda| pda_doc(&name, pda, pdas, &optional, &derived_names, accounts_struct));
+            key_fields.push(quote! {
+                #docs
+                #pda_doc
+                pub #ident: #ty,
+            });
+            locals.push(quote! { let #local = keys.#ident; });
+        }
+    }
+    for pda in &derived {
+        let ident = local(&pda.account);
+        let fn_ident = &pda.fn_ident;
+        let call_args = pda.params.iter().map(|(source, by_value)| match source {
+            Source::Account(account) => {
+                let account = local(account);
+                quote! { &#account }
+            }
+            Source::Arg(path) => {
+                let path = path
+                    .iter()
+                    .map(|part| syn::Ident::new(part, proc_macro2::Span::call_site()));
+                if *by_value {
+                    quote! { self #( .#path )* }
+                } else {
+                    quote! { &self #( .#path )* }
+                }
+            }
+            Source::AccountField { .. } => unreachable!("PDAs seeded by account data are keys"),
+        });
+        locals.push(quote! {
+            let (#ident, _) = #accounts_struct::#fn_ident( #( #call_args ),* );
+        });
+    }
+
+    let metas = accounts.iter().map(|account| {
+        let ident = local(&account_name(account));
+        let is_signer = flag(account, &["signer", "isSigner"]);
+        let is_writable = flag(account, &["writable", "isMut"]);
+        let meta = |pubkey| {
+            quote! {
+                ::solana_sdk::instruction::AccountMeta {
+                    pubkey: #pubkey,
+                    is_signer: #is_signer,
+                    is_writable: #is_writable,
+                }
+            }
+        };
+        if optional.contains(&account_name(account)) && !fixed.contains(&account_name(account)) {
+            // Anchor expects the program ID in place of an optional account that's left out.
+            let present = meta(quote! { pubkey });
+            quote! {
+                match #ident {
+                    Some(pubkey) => #present,
+                    None => ::solana_sdk::instruction::AccountMeta::new_readonly(ID, false),
+                }
+            }
+        } else {
+            meta(quote! { #ident })
+        }
+    });
+
+    let data = if instruction
+        .get("args")
+        .and_then(|v| v.as_array())
+        .is_some_and(|args| !args.is_empty())
+    {
+        quote! {
+            let mut data = Self::DISCRIMINATOR.to_vec();
+            ::borsh::BorshSerialize::serialize(self, &mut data)
+                .expect("serializing into a Vec doesn't fail");
+        }
+    } else {
+        quote! { let data = Self::DISCRIMINATOR.to_vec(); }
+    };
+    let unused_keys = key_fields.is_empty().then(|| quote! { let _ = keys; });
+
+    let keys_doc = format!(
+        " Accounts of [`{}`] that [`{}::instruction`] can't fill in or derive",
+        struct_name, struct_name
+    );
+    Some(quote! {
+        #[doc = #keys_doc]
+        #[derive(Debug, Clone, Copy)]
+        pub struct #keys_struct {
+            #( #key_fields )*
+        }
+
+        impl #struct_name {
+            /// Builds the instruction from its args and the accounts in `keys`. Accounts with a
+            /// fixed address in the IDL are filled in, and PDA accounts are derived from their
+            /// seeds.
+            pub fn instruction(&self, keys: &#keys_struct) -> ::solana_sdk::instruction::Instruction {
+                #unused_keys
+                #( #locals )*
+                #data
+                ::solana_sdk::instruction::Instruction {
+                    program_id: ID,
+                    accounts: vec![ #( #metas ),* ],
+                    data,
+                }
+            }
+        }
+    })
+}
+
+/// Generates the doc of a PDA account that has to be passed in the keys struct, saying which of
+/// its seeds keeps it from being derived
+fn pda_doc(
+    name: &str,
+    pda: &Value,
+    pdas: &InstructionPdas,
+    optional: &HashSet<String>,
+    derived: &HashSet<&str>,
+    accounts_struct: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let Some(derivation) = pdas.derivations.iter().find(|pda| pda.account == name) else {
+        let seeds: Vec<String> = list(pda, "seeds")
+            .iter()
+            .filter_map(|seed| Some(format!("`{}`", seed.get("path")?.as_str()?)))
+            .collect();
+        let doc = format!(
+            " PDA that can't be derived, since its seeds ({}) or program include a kind or type \
+             this crate doesn't support",
+            seeds.join(", ")
+        );
+        return quote! { #[doc = #doc] };
+    };
+    let seeds = |keep: &dyn Fn(&Source) -> Option<String>| -> Vec<String> {
+        derivation
+            .params
+            .iter()
+            .filter_map(|(source, _)| keep(source))
+            .collect()
+    };
+    let fetched = seeds(&|source| match source {
+        Source::AccountField { account, field } => Some(format!("`{}.{}`", account, field)),
+        _ => None,
+    });
+    let optional_seeds = seeds(&|source| match source {
+        Source::Account(account) if optional.contains(account) => Some(format!("`{}`", account)),
+        _ => None,
+    });
+    let underived_seeds = seeds(&|source| match source {
+        Source::Account(account)
+            if !derived.contains(account.as_str())
+                && pdas.derivations.iter().any(|pda| pda.account == *account) =>
+        {
+            Some(format!("`{}`", account))
+        }
+        _ => None,
+    });
+    let reason = if optional.contains(name) {
+        "it's optional".to_string()
+    } else if !fetched.is_empty() {
+        format!(
+            "it's seeded by {}, which must be fetched",
+            fetched.join(", ")
+        )
+    } else if !optional_seeds.is_empty() {
+        format!(
+            "it's seeded by the optional account {}",
+            optional_seeds.join(", ")
+        )
+    } else {
+        format!(
+            "it's seeded by {}, a PDA that can't be derived here either",
+            underived_seeds.join(", ")
+        )
+    };
+    let doc = format!(
+        " PDA that can't be derived here, since {}; see `{}::{}`",
+        reason, accounts_struct, derivation.fn_ident
+    );
+    quote! { #[doc = #doc] }
+}
+
+fn flag(account: &Value, keys: &[&str]) -> bool {
+    keys.iter()
+        .any(|key| account.get(*key).and_then(|v| v.as_bool()).unwrap_or(false))
+}
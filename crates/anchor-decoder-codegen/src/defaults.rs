@@ -0,0 +1,76 @@
+use quote::quote;
+use serde_json::Value;
+
+use crate::idl::{ident, name};
+
+/// Generates the `Default` impl of a struct with named fields, each set to its type's default.
+/// It's written out rather than derived, since arrays longer than 32 don't implement `Default`.
+pub fn struct_default(type_ident: &syn::Ident, fields: &[Value]) -> proc_macro2::TokenStream {
+    let fields = fields.iter().filter_map(|field| {
+        let field_ident = ident(name(field)?);
+        let value = default_value(field.get("type")?);
+        Some(quote! { #field_ident: #value })
+    });
+    default_impl(type_ident, quote! { Self { #( #fields ),* } })
+}
+
+/// Generates the `Default` impl of an enum IDL type, which defaults to its first variant with
+/// every field set to its type's default. Enums without variants get no impl.
+pub fn enum_default(type_ident: &syn::Ident, variants: &[Value]) -> proc_macro2::TokenStream {
+    let Some(variant) = variants.first() else {
+        return quote! {};
+    };
+    let Some(variant_name) = name(variant) else {
+        return quote! {};
+    };
+    let variant_ident = ident(variant_name);
+    let fields = variant
+        .get("fields")
+        .and_then(|v| v.as_array())
+        .filter(|fields| !fields.is_empty());
+    let value = match fields {
+        Some(fields) if fields[0].get("name").is_some() => {
+            let fields = fields.iter().filter_map(|field| {
+                let field_ident = ident(name(field)?);
+                let value = default_value(field.get("type")?);
+                Some(quote! { #field_ident: #value })
+            });
+            quote! { Self::#variant_ident { #( #fields ),* } }
+        }
+        Some(fields) => {
+            let values = fields.iter().map(default_value);
+            quote! { Self::#variant_ident( #( #values ),* ) }
+        }
+        None => quote! { Self::#variant_ident },
+    };
+    default_impl(type_ident, value)
+}
+
+fn default_impl(
+    type_ident: &syn::Ident,
+    value: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        impl ::core::default::Default for #type_ident {
+            fn default() -> Self {
+                #value
+            }
+        }
+    }
+}
+
+/// The default value of an IDL type. Arrays are built element by element, so they don't need
+/// `Default` themselves.
+fn default_value(ty: &Value) -> proc_macro2::TokenStream {
+    match ty
+        .get("array")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+    {
+        Some([inner, _]) => {
+            let inner = default_value(inner);
+            quote! { ::core::array::from_fn(|_| #inner) }
+        }
+        _ => quote! { ::core::default::Default::default() },
+    }
+}
@@ -0,0 +1,652 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::docs::without_docs;
+use crate::idl::{account_names, display_name, flat_accounts, list};
+
+/// How a change between two IDL versions affects code and data decoded with the old one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Impact {
+    /// Data encoded with one IDL no longer decodes the same way with the other, or a generated
+    /// item that code may use was removed or renamed
+    LayoutBreaking,
+    /// An instruction's accounts moved, so account indices and names resolved with the old IDL
+    /// point at the wrong keys
+    AccountIndexBreaking,
+    /// An instruction account's signer, writable or optional flag or fixed address changed, so
+    /// instructions built and constraints checked with the old IDL no longer match
+    AccountConstraintBreaking,
+    /// Something was added without changing how existing data decodes
+    Additive,
+}
+
+impl fmt::Display for Impact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Impact::LayoutBreaking => "layout-breaking",
+            Impact::AccountIndexBreaking => "account-index-breaking",
+            Impact::AccountConstraintBreaking => "account-constraint-breaking",
+            Impact::Additive => "additive",
+        })
+    }
+}
+
+/// A single difference between two IDLs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdlChange {
+    pub impact: Impact,
+    /// The item that changed, e.g. "instruction `swap`" or "type `Position`"
+    pub item: String,
+    /// What changed, e.g. "arg `amount` changed from u64 to u128"
+    pub description: String,
+}
+
+impl fmt::Display for IdlChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.item, self.description, self.impact)
+    }
+}
+
+/// Compares two Anchor IDLs and classifies every change to instructions, accounts, events and
+/// types. Docs are ignored.
+///
+/// Instruction arguments and type fields are compared in order, since Borsh encodes them by
+/// position: anything other than a new enum variant at the end changes the layout. Instruction
+/// accounts are compared by position; accounts appended at the end are additive, anything else
+/// breaks account indices. Accounts kept in both are also compared by their signer, writable and
+/// optional flags and fixed address. Removed items whose discriminator reappears under another
+/// name are reported as renames.
+pub fn diff_idls(old: &Value, new: &Value) -> Vec<IdlChange> {
+    let mut changes = Changes::default();
+
+    let address = |idl: &Value| {
+        idl.get("address")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    if address(old) != address(new) {
+        changes.push(
+            Impact::LayoutBreaking,
+            "program".to_string(),
+            format!(
+                "address changed from {} to {}",
+                address(old).unwrap_or_default(),
+                address(new).unwrap_or_default()
+            ),
+        );
+    }
+
+    diff_items(
+        &mut changes,
+        "instruction",
+        list(old, "instructions"),
+        list(new, "instructions"),
+        |changes, item, old, new| {
+            diff_fields(
+                changes,
+                item,
+                "arg",
+                list(old, "args"),
+                list(new, "args"),
+                false,
+            );
+            diff_accounts(changes, item, old, new);
+        },
+    );
+    diff_items(
+        &mut changes,
+        "account",
+        list(old, "accounts"),
+        list(new, "accounts"),
+        |_, _, _, _| {},
+    );
+    diff_items(
+        &mut changes,
+        "event",
+        list(old, "events"),
+        list(new, "events"),
+        |_, _, _, _| {},
+    );
+    diff_items(
+        &mut changes,
+        "type",
+        list(old, "types"),
+        list(new, "types"),
+        diff_type,
+    );
+
+    changes.0
+}
+
+#[derive(Default)]
+struct Changes(Vec<IdlChange>);
+
+impl Changes {
+    fn push(&mut self, impact: Impact, item: String, description: String) {
+        self.0.push(IdlChange {
+            impact,
+            item,
+            description,
+        });
+    }
+}
+
+/// Matches items by name, reporting removed, renamed and added items and changed
+/// discriminators, and calls `diff` on each pair present in both
+fn diff_items(
+    changes: &mut Changes,
+    kind: &str,
+    old: &[Value],
+    new: &[Value],
+    diff: impl Fn(&mut Changes, &str, &Value, &Value),
+) {
+    let new_by_name: HashMap<&str, &Value> =
+        new.iter().map(|item| (display_name(item), item)).collect();
+    let old_by_name: HashMap<&str, &Value> =
+        old.iter().map(|item| (display_name(item), item)).collect();
+    let discriminator = |item: &Value| item.get("discriminator").filter(|d| !d.is_null()).cloned();
+
+    for old_item in old {
+        let item = format!("{} `{}`", kind, display_name(old_item));
+        match new_by_name.get(display_name(old_item)) {
+            Some(new_item) => {
+                if discriminator(old_item) != discriminator(new_item) {
+                    changes.push(
+                        Impact::LayoutBreaking,
+                        item.clone(),
+                        format!(
+                            "discriminator changed from {} to {}",
+                            discriminator(old_item).unwrap_or_default(),
+                            discriminator(new_item).unwrap_or_default()
+                        ),
+                    );
+                }
+                diff(changes, &item, old_item, new_item);
+            }
+            None => {
+                let renamed = discriminator(old_item).and_then(|old_disc| {
+                    new.iter().find(|new_item| {
+                        !old_by_name.contains_key(display_name(new_item))
+                            && discriminator(new_item).as_ref() == Some(&old_disc)
+                    })
+                });
+                let description = match renamed {
+                    Some(new_item) => format!("renamed to `{}`", display_name(new_item)),
+                    None => "removed".to_string(),
+                };
+                changes.push(Impact::LayoutBreaking, item, description);
+            }
+        }
+    }
+
+    for new_item in new {
+        if old_by_name.contains_key(display_name(new_item)) {
+            continue;
+        }
+        // Renames were reported with the old item.
+        let renamed = discriminator(new_item).is_some_and(|new_disc| {
+            old.iter().any(|old_item| {
+                !new_by_name.contains_key(display_name(old_item))
+                    && discriminator(old_item).as_ref() == Some(&new_disc)
+            })
+        });
+        if !renamed {
+            changes.push(
+                Impact::Additive,
+                format!("{} `{}`", kind, display_name(new_item)),
+                "added".to_string(),
+            );
+        }
+    }
+}
+
+/// Compares an IDL `types` entry, field by field for structs and variant by variant for enums
+fn diff_type(changes: &mut Changes, item: &str, old: &Value, new: &Value) {
+    let type_info = |def: &Value| def.get("type").cloned().unwrap_or_default();
+    let (old, new) = (type_info(old), type_info(new));
+    let kind = |info: &Value| {
+        info.get("kind")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string()
+    };
+
+    match (kind(&old).as_str(), kind(&new).as_str()) {
+        ("struct", "struct") => diff_fields(
+            changes,
+            item,
+            "field",
+            list(&old, "fields"),
+            list(&new, "fields"),
+            false,
+        ),
+        ("enum", "enum") => diff_fields(
+            changes,
+            item,
+            "variant",
+            list(&old, "variants"),
+            list(&new, "variants"),
+            true,
+        ),
+        (old_kind, new_kind) if old_kind != new_kind => changes.push(
+            Impact::LayoutBreaking,
+            item.to_string(),
+            format!("changed from {} to {}", old_kind, new_kind),
+        ),
+        _ => {
+            if without_docs(&old) != without_docs(&new) {
+                changes.push(
+                    Impact::LayoutBreaking,
+                    item.to_string(),
+                    "definition changed".to_string(),
+                );
+            }
+        }
+    }
+}
+
+/// Compares fields (or args, or enum variants), which Borsh encodes by position. For enums,
+/// variants appended at the end are additive, since existing data never uses their index.
+fn diff_fields(
+    changes: &mut Changes,
+    item: &str,
+    what: &str,
+    old: &[Value],
+    new: &[Value],
+    appending_is_additive: bool,
+) {
+    let old_names: Vec<&str> = old.iter().map(display_name).collect();
+    let new_names: Vec<&str> = new.iter().map(display_name).collect();
+
+    for removed in old_names.iter().filter(|n| !new_names.contains(n)) {
+        changes.push(
+            Impact::LayoutBreaking,
+            item.to_string(),
+            format!("{} `{}` removed", what, removed),
+        );
+    }
+    for (position, added) in new_names.iter().enumerate() {
+        if old_names.contains(added) {
+            continue;
+        }
+        let impact = if appending_is_additive && position >= old.len() {
+            Impact::Additive
+        } else {
+            Impact::LayoutBreaking
+        };
+        changes.push(
+            impact,
+            item.to_string(),
+            format!("{} `{}` added at position {}", what, added, position),
+        );
+    }
+
+    // Fields kept in both should stay in the same order relative to each other.
+    let kept_old: Vec<&str> = old_names
+        .iter()
+        .copied()
+        .filter(|n| new_names.contains(n))
+        .collect();
+    let kept_new: Vec<&str> = new_names
+        .iter()
+        .copied()
+        .filter(|n| old_names.contains(n))
+        .collect();
+    for (old_position, field) in kept_old.iter().enumerate() {
+        let new_position = kept_new.iter().position(|n| n == field).unwrap_or_default();
+        if old_position != new_position {
+            changes.push(
+                Impact::LayoutBreaking,
+                item.to_string(),
+                format!("{} `{}` reordered", what, field),
+            );
+        }
+    }
+
+    for old_field in old {
+        let Some(new_field) = new
+            .iter()
+            .find(|f| display_name(f) == display_name(old_field))
+        else {
+            continue;
+        };
+        if without_docs(old_field) == without_docs(new_field) {
+            continue;
+        }
+        let description = match (old_field.get("type"), new_field.get("type")) {
+            (Some(old_type), Some(new_type)) => format!(
+                "{} `{}` changed from {} to {}",
+                what,
+                display_name(old_field),
+                type_name(old_type),
+                type_name(new_type)
+            ),
+            _ => format!("{} `{}` changed", what, display_name(old_field)),
+        };
+        changes.push(Impact::LayoutBreaking, item.to_string(), description);
+    }
+}
+
+/// Compares an instruction's accounts, which are passed by index, and the constraints on each.
+/// Composite account groups are flattened into `<group>.<account>` names.
+fn diff_accounts(changes: &mut Changes, item: &str, old: &Value, new: &Value) {
+    let new_accounts = flat_accounts(new);
+    for (account, old_account) in flat_accounts(old) {
+        let Some((_, new_account)) = new_accounts.iter().find(|(n, _)| *n == account) else {
+            continue;
+        };
+        // Reads both the current flags and the legacy `isSigner`, `isMut` and `isOptional` ones.
+        let constraints = |account: &Value| {
+            let flag = |keys: [&str; 2]| {
+                keys.iter()
+                    .any(|key| account.get(*key).and_then(|v| v.as_bool()).unwrap_or(false))
+                    .to_string()
+            };
+            [
+                ("signer", flag(["signer", "isSigner"])),
+                ("writable", flag(["writable", "isMut"])),
+                ("optional", flag(["optional", "isOptional"])),
+                (
+                    "address",
+                    account
+                        .get("address")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("none")
+                        .to_string(),
+                ),
+            ]
+        };
+        for ((what, old_value), (_, new_value)) in constraints(old_account)
+            .into_iter()
+            .zip(constraints(new_account))
+        {
+            if old_value != new_value {
+                changes.push(
+                    Impact::AccountConstraintBreaking,
+                    item.to_string(),
+                    format!(
+                        "account `{}` {} changed from {} to {}",
+                        account, what, old_value, new_value
+                    ),
+                );
+            }
+        }
+    }
+
+    let (old, new) = (account_names(old), account_names(new));
+    for (old_index, account) in old.iter().enumerate() {
+        let description = match new.iter().position(|n| n == account) {
+            Some(new_index) if new_index == old_index => continue,
+            Some(new_index) => format!(
+                "account `{}` moved from index {} to {}",
+                account, old_index, new_index
+            ),
+            None => format!("account `{}` removed from index {}", account, old_index),
+        };
+        changes.push(Impact::AccountIndexBreaking, item.to_string(), description);
+    }
+    for (index, added) in new.iter().enumerate() {
+        if old.contains(added) {
+            continue;
+        }
+        let impact = if index >= old.len() {
+            Impact::Additive
+        } else {
+            Impact::AccountIndexBreaking
+        };
+        changes.push(
+            impact,
+            item.to_string(),
+            format!("account `{}` added at index {}", added, index),
+        );
+    }
+}
+
+/// Renders an IDL type the way it's generated, e.g. `Vec<u8>` or `[u64; 4]`
+fn type_name(ty: &Value) -> String {
+    if let Some(s) = ty.as_str() {
+        return match s {
+            "pubkey" => "Pubkey".to_string(),
+            "string" => "String".to_string(),
+            "bytes" => "Vec<u8>".to_string(),
+            s => s.to_string(),
+        };
+    }
+    if let Some(inner) = ty.get("vec") {
+        return format!("Vec<{}>", type_name(inner));
+    }
+    if let Some(inner) = ty.get("option") {
+        return format!("Option<{}>", type_name(inner));
+    }
+    if let Some([inner, len]) = ty
+        .get("array")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+    {
+        return format!("[{}; {}]", type_name(inner), len);
+    }
+    if let Some(defined) = ty.get("defined") {
+        if let Some(name) = defined
+            .get("name")
+            .or(Some(defined))
+            .and_then(|v| v.as_str())
+        {
+            return name.to_string();
+        }
+    }
+    ty.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn instruction(name: &str, args: &[&str], accounts: &[&str]) -> Value {
+        let args: Vec<Value> = args
+            .iter()
+            .map(|arg| json!({ "name": arg, "type": "u64" }))
+            .collect();
+        let accounts: Vec<Value> = accounts
+            .iter()
+            .map(|account| json!({ "name": account }))
+            .collect();
+        json!({ "name": name, "discriminator": [1, 2], "args": args, "accounts": accounts })
+    }
+
+    fn with_instruction(instruction: Value) -> Value {
+        json!({ "address": "11111111111111111111111111111111", "instructions": [instruction] })
+    }
+
+    fn with_type(kind: &str, key: &str, names: &[&str]) -> Value {
+        let members: Vec<Value> = names
+            .iter()
+            .map(|name| json!({ "name": name, "type": "u8" }))
+            .collect();
+        json!({
+            "address": "11111111111111111111111111111111",
+            "types": [{ "name": "Pool", "type": { "kind": kind, key: members } }],
+        })
+    }
+
+    fn changes(old: &Value, new: &Value) -> Vec<(Impact, String)> {
+        diff_idls(old, new)
+            .into_iter()
+            .map(|change| {
+                (
+                    change.impact,
+                    format!("{}: {}", change.item, change.description),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn arg_appended() {
+        let old = with_instruction(instruction("swap", &["amount"], &[]));
+        let new = with_instruction(instruction("swap", &["amount", "slippage"], &[]));
+        assert_eq!(
+            changes(&old, &new),
+            [(
+                Impact::LayoutBreaking,
+                "instruction `swap`: arg `slippage` added at position 1".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn field_reordered() {
+        let old = with_type("struct", "fields", &["a", "b", "c"]);
+        let new = with_type("struct", "fields", &["a", "c", "b"]);
+        assert_eq!(
+            changes(&old, &new),
+            [
+                (
+                    Impact::LayoutBreaking,
+                    "type `Pool`: field `b` reordered".to_string()
+                ),
+                (
+                    Impact::LayoutBreaking,
+                    "type `Pool`: field `c` reordered".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn variant_appended_or_inserted() {
+        let old = with_type("enum", "variants", &["A", "B"]);
+        let appended = with_type("enum", "variants", &["A", "B", "C"]);
+        let inserted = with_type("enum", "variants", &["A", "C", "B"]);
+        assert_eq!(
+            changes(&old, &appended),
+            [(
+                Impact::Additive,
+                "type `Pool`: variant `C` added at position 2".to_string()
+            )]
+        );
+        assert_eq!(
+            changes(&old, &inserted),
+            [(
+                Impact::LayoutBreaking,
+                "type `Pool`: variant `C` added at position 1".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn accounts_moved_removed_and_appended() {
+        let old = with_instruction(instruction("swap", &[], &["pool", "vault", "owner"]));
+        let new = with_instruction(instruction("swap", &[], &["owner", "pool", "oracle"]));
+        assert_eq!(
+            changes(&old, &new),
+            [
+                (
+                    Impact::AccountIndexBreaking,
+                    "instruction `swap`: account `pool` moved from index 0 to 1".to_string()
+                ),
+                (
+                    Impact::AccountIndexBreaking,
+                    "instruction `swap`: account `vault` removed from index 1".to_string()
+                ),
+                (
+                    Impact::AccountIndexBreaking,
+                    "instruction `swap`: account `owner` moved from index 2 to 0".to_string()
+                ),
+                (
+                    Impact::AccountIndexBreaking,
+                    "instruction `swap`: account `oracle` added at index 2".to_string()
+                ),
+            ]
+        );
+
+        let appended = with_instruction(instruction(
+            "swap",
+            &[],
+            &["pool", "vault", "owner", "oracle"],
+        ));
+        assert_eq!(
+            changes(&old, &appended),
+            [(
+                Impact::Additive,
+                "instruction `swap`: account `oracle` added at index 3".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn account_constraints_changed() {
+        let old = with_instruction(instruction("swap", &[], &["pool", "owner", "program"]));
+        let mut new = old.clone();
+        new["instructions"][0]["accounts"][0]["writable"] = json!(true);
+        new["instructions"][0]["accounts"][1]["isSigner"] = json!(true);
+        new["instructions"][0]["accounts"][2]["address"] =
+            json!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+        assert_eq!(
+            changes(&old, &new),
+            [
+                (
+                    Impact::AccountConstraintBreaking,
+                    "instruction `swap`: account `pool` writable changed from false to true"
+                        .to_string()
+                ),
+                (
+                    Impact::AccountConstraintBreaking,
+                    "instruction `swap`: account `owner` signer changed from false to true"
+                        .to_string()
+                ),
+                (
+                    Impact::AccountConstraintBreaking,
+                    "instruction `swap`: account `program` address changed from none to \
+                     whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_detected_by_discriminator() {
+        let old = with_instruction(instruction("swap", &["amount"], &[]));
+        let new = with_instruction(instruction("swap_exact", &["amount"], &[]));
+        assert_eq!(
+            changes(&old, &new),
+            [(
+                Impact::LayoutBreaking,
+                "instruction `swap`: renamed to `swap_exact`".to_string()
+            )]
+        );
+
+        let mut replaced = instruction("deposit", &[], &[]);
+        replaced["discriminator"] = json!([3, 4]);
+        assert_eq!(
+            changes(&old, &with_instruction(replaced)),
+            [
+                (
+                    Impact::LayoutBreaking,
+                    "instruction `swap`: removed".to_string()
+                ),
+                (Impact::Additive, "instruction `deposit`: added".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn docs_ignored() {
+        let old = with_instruction(instruction("swap", &["amount"], &["pool"]));
+        let mut new = old.clone();
+        new["instructions"][0]["docs"] = json!(["Swaps tokens"]);
+        new["instructions"][0]["args"][0]["docs"] = json!(["Amount in"]);
+        new["instructions"][0]["accounts"][0]["docs"] = json!(["The pool"]);
+        assert_eq!(changes(&old, &new), []);
+
+        let old = with_type("struct", "fields", &["a"]);
+        let mut new = old.clone();
+        new["types"][0]["docs"] = json!(["A pool"]);
+        new["types"][0]["type"]["fields"][0]["docs"] = json!(["The a"]);
+        assert_eq!(changes(&old, &new), []);
+    }
+}
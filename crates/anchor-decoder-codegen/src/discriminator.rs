@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use quote::quote;
+use serde_json::Value;
+
+/// Discriminator of Anchor's emit_cpi instruction. Some programs call it to emit events via
+/// self-CPI, so that subscribed clients can see the events without risk of the RPC truncating
+/// them (as with traditional event logging).
+///
+/// Source: https://github.com/coral-xyz/anchor/blob/8b391aa278387b6f6ce3133453619a175544631e/lang/attribute/event/src/lib.rs#L111-L195
+pub const EMIT_CPI_INSTRUCTION_DISCRIMINATOR: [u8; 8] = [228, 69, 165, 46, 81, 203, 154, 29];
+
+/// Reads the `discriminator` array of an IDL instruction, account or event. Anchor defaults to
+/// 8 bytes, but custom discriminators (and IDLs converted from Shank or Codama) may use any
+/// non-zero length.
+pub fn read_discriminator(item: &Value) -> Result<Vec<u8>, String> {
+    let discriminator = item
+        .get("discriminator")
+        .and_then(|v| v.as_array())
+        .ok_or("discriminator missing or not an array")?;
+    if discriminator.is_empty() {
+        return Err("empty discriminator".to_string());
+    }
+    discriminator
+        .iter()
+        .map(|v| {
+            v.as_u64()
+                .filter(|b| *b <= u8::MAX as u64)
+                .map(|b| b as u8)
+                .ok_or_else(|| format!("invalid discriminator byte {}", v))
+        })
+        .collect()
+}
+
+/// Like [`read_discriminator`], for IDLs that have already been validated
+pub fn parse_discriminator(item: &Value, kind: &str, name: &str) -> Vec<u8> {
+    read_discriminator(item).unwrap_or_else(|e| panic!("{} `{}`: {}", kind, name, e))
+}
+
+/// Returns the index pairs of discriminators that can't be told apart by their prefix. Since
+/// items may have discriminators of different lengths, a discriminator that is a prefix of another
+/// (including an identical one) would make dispatch depend on the order arms are tried in.
+pub fn overlapping_discriminators(items: &[(String, Vec<u8>)]) -> Vec<(usize, usize)> {
+    let mut overlapping = Vec::new();
+    for (i, (_, a)) in items.iter().enumerate() {
+        for (j, (_, b)) in items.iter().enumerate().skip(i + 1) {
+            if a.starts_with(b) || b.starts_with(a) {
+                overlapping.push((i, j));
+            }
+        }
+    }
+    overlapping
+}
+
+/// Generates prefix dispatch over `data` for a set of discriminators. Each entry pairs a
+/// discriminator with the statements to run when it matches, which must `return`.
+///
+/// Entries are grouped by discriminator length. Groups of up to 8 bytes read the prefix as a
+/// little-endian `u64` and dispatch with an integer `match`; longer discriminators fall back to a
+/// slice pattern. Because IDL validation guarantees no discriminator is a prefix of
+/// another, at most one group can match and the order groups are tried in doesn't matter.
+pub fn dispatch(entries: Vec<(Vec<u8>, proc_macro2::TokenStream)>) -> proc_macro2::TokenStream {
+    if entries.is_empty() {
+        return quote! { let _ = data; };
+    }
+
+    let mut groups: BTreeMap<usize, Vec<(Vec<u8>, proc_macro2::TokenStream)>> = BTreeMap::new();
+    for (disc, body) in entries {
+        groups.entry(disc.len()).or_default().push((disc, body));
+    }
+
+    // Try the longest discriminators first, which are typically the Anchor defaults.
+    let group_matches = groups.into_iter().rev().map(|(len, entries)| {
+        if len <= 8 {
+            let arms = entries.iter().map(|(disc, body)| {
+                let literal = discriminator_literal(disc);
+                quote! { #literal => { #body } }
+            });
+            quote! {
+                if let Some(disc) = __anchor_decoder::read_discriminator(data, #len) {
+                    match disc {
+                        #( #arms )*
+                        _ => {}
+                    }
+                }
+            }
+        } else {
+            let arms = entries.iter().map(|(disc, body)| {
+                quote! { [ #( #disc ),* ] => { #body } }
+            });
+            quote! {
+                if let Some(disc) = data.get(..#len) {
+                    match disc {
+                        #( #arms )*
+                        _ => {}
+                    }
+                }
+            }
+        }
+    });
+
+    quote! { #( #group_matches )* }
+}
+
+/// Converts a discriminator of up to 8 bytes into the little-endian `u64` literal that
+/// `read_discriminator` produces for it, so the generated decoders can dispatch with an integer
+/// `match` rather than comparing slices one arm at a time.
+pub fn discriminator_literal(disc: &[u8]) -> proc_macro2::Literal {
+    let mut bytes = [0u8; 8];
+    bytes[..disc.len()].copy_from_slice(disc);
+    proc_macro2::Literal::u64_suffixed(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(discriminators: &[&[u8]]) -> Vec<(String, Vec<u8>)> {
+        discriminators
+            .iter()
+            .enumerate()
+            .map(|(i, disc)| (format!("item_{}", i), disc.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn distinct_discriminators() {
+        let items = items(&[&[1, 2], &[1, 3], &[2], &[3, 1, 2]]);
+        assert_eq!(overlapping_discriminators(&items), []);
+    }
+
+    #[test]
+    fn prefixes_and_duplicates() {
+        let items = items(&[&[1], &[2, 2], &[1, 2, 3], &[2, 2], &[3]]);
+        assert_eq!(overlapping_discriminators(&items), [(0, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn empty_discriminator_overlaps_everything() {
+        let items = items(&[&[], &[1], &[2]]);
+        assert_eq!(overlapping_discriminators(&items), [(0, 1), (0, 2)]);
+    }
+}
@@ -0,0 +1,55 @@
+use quote::quote;
+use serde_json::Value;
+
+/// Converts the `docs` array of an IDL item (instruction, account, arg, field, type or variant)
+/// into `#[doc]` attributes, one per line.
+pub fn doc_attrs(item: &Value) -> proc_macro2::TokenStream {
+    let lines = item
+        .get("docs")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|line| line.as_str())
+        .map(|line| format!(" {}", line));
+    quote! { #( #[doc = #lines] )* }
+}
+
+/// Describes the program from the IDL `metadata` (name, version, description), its address and
+/// any top-level `docs`, as lines of Markdown.
+pub fn program_doc_lines(idl: &Value) -> Vec<String> {
+    let metadata = idl.get("metadata");
+    let field = |key: &str| metadata.and_then(|m| m.get(key)).and_then(|v| v.as_str());
+
+    let mut lines = vec![match (field("name"), field("version")) {
+        (Some(name), Some(version)) => format!(" Decoder for the `{}` program (v{}).", name, version),
+        (Some(name), None) => format!(" Decoder for the `{}` program.", name),
+        _ => " Decoder for an Anchor program.".to_string(),
+    }];
+    if let Some(address) = idl.get("address").and_then(|v| v.as_str()) {
+        lines.push(String::new());
+        lines.push(format!(" Program address: `{}`", address));
+    }
+    if let Some(description) = field("description") {
+        lines.push(String::new());
+        lines.push(format!(" {}", description));
+    }
+    if let Some(docs) = idl.get("docs").and_then(|v| v.as_array()) {
+        lines.push(String::new());
+        lines.extend(docs.iter().filter_map(|v| v.as_str()).map(|line| format!(" {}", line)));
+    }
+    lines
+}
+
+/// Strips `docs` from an IDL item and everything in it, to compare items by what they encode
+pub fn without_docs(value: &Value) -> Value {
+    match value {
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .filter(|(key, _)| *key != "docs")
+                .map(|(key, value)| (key.clone(), without_docs(value)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(without_docs).collect()),
+        value => value.clone(),
+    }
+}
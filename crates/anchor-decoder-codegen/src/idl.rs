@@ -0,0 +1,69 @@
+//! Accessors for the IDL JSON, shared by the generators.
+
+use serde_json::Value;
+
+/// Returns the array under `key`, or an empty slice if it's missing
+pub(crate) fn list<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+pub(crate) fn name(item: &Value) -> Option<&str> {
+    item.get("name").and_then(|v| v.as_str())
+}
+
+/// Returns the item's name for messages, or `unknown` if it has none
+pub(crate) fn display_name(item: &Value) -> &str {
+    name(item).unwrap_or("unknown")
+}
+
+pub(crate) fn ident(name: &str) -> syn::Ident {
+    syn::Ident::new(name, proc_macro2::Span::call_site())
+}
+
+/// Flattens an instruction's accounts in order, expanding composite account groups. Accounts in
+/// a group are named `<group>.<account>`, and take as many slots in the instruction as the group
+/// has accounts.
+pub(crate) fn flat_accounts(instruction: &Value) -> Vec<(String, &Value)> {
+    fn collect<'a>(accounts: &'a [Value], prefix: &str, flat: &mut Vec<(String, &'a Value)>) {
+        for account in accounts {
+            let account_name = format!("{}{}", prefix, display_name(account));
+            match account.get("accounts").and_then(|v| v.as_array()) {
+                Some(nested) => collect(nested, &format!("{}.", account_name), flat),
+                None => flat.push((account_name, account)),
+            }
+        }
+    }
+
+    let mut flat = Vec::new();
+    collect(list(instruction, "accounts"), "", &mut flat);
+    flat
+}
+
+/// Flattens an instruction's accounts into their names in order. Composite account groups are
+/// expanded, with each account named `<group>.<account>`.
+pub fn account_names(instruction: &Value) -> Vec<String> {
+    flat_accounts(instruction)
+        .into_iter()
+        .map(|(account_name, _)| account_name)
+        .collect()
+}
+
+/// Returns the ident of the field generated for a flattened account name, e.g. `trade_owner`
+/// for `trade.owner`
+pub(crate) fn account_field(account_name: &str) -> syn::Ident {
+    ident(&account_name.replace('.', "_"))
+}
+
+/// Returns the ident of the index constant generated for a flattened account name, e.g.
+/// `TRADE_OWNER` for `trade.owner`
+pub(crate) fn account_const(account_name: &str) -> syn::Ident {
+    ident(&account_const_name(account_name))
+}
+
+pub(crate) fn account_const_name(account_name: &str) -> String {
+    account_name.replace('.', "_").to_uppercase()
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{map_idl_type, to_snake_case};
+
+/// Computes the static size of every IDL `types` entry, keyed by name. Variable-size types
+/// (and types that reference them) map to `None`.
+///
+/// Sizes are resolved to a fixed point so that a type can reference types defined after it,
+/// and so that self-referencing types terminate as variable-size instead of recursing forever.
+pub fn type_sizes(types: &[Value]) -> HashMap<String, Option<usize>> {
+    let mut sizes: HashMap<String, Option<usize>> = types
+        .iter()
+        .filter_map(|t| t.get("name").and_then(|v| v.as_str()))
+        .map(|name| (name.to_string(), None))
+        .collect();
+
+    loop {
+        let mut changed = false;
+        for type_def in types {
+            let Some(name) = type_def.get("name").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            if sizes[name].is_some() {
+                continue;
+            }
+            if let Some(size) = type_def_size(type_def, &sizes) {
+                sizes.insert(name.to_string(), Some(size));
+                changed = true;
+            }
+        }
+        if !changed {
+            return sizes;
+        }
+    }
+}
+
+/// Returns the serialized size of an IDL `types` entry, or `None` if it is variable-size or its
+/// layout isn't in the IDL.
+pub fn type_def_size(
+    type_def: &Value,
+    generated_types: &HashMap<String, Option<usize>>,
+) -> Option<usize> {
+    let type_info = type_def.get("type")?;
+    match type_info.get("kind").and_then(|v| v.as_str())? {
+        // A struct without `fields` had its layout left out of the IDL, so its size is unknown.
+        "struct" => fields_size(
+            type_info.get("fields").and_then(|v| v.as_array())?,
+            generated_types,
+        ),
+        "enum" => {
+            // Borsh encodes the variant index as a single byte, followed by the variant's fields.
+            // The enum only has a static size if every variant has the same size.
+            let variants = type_info.get("variants").and_then(|v| v.as_array())?;
+            let mut size = None;
+            for variant in variants {
+                let variant_size = match variant.get("fields").and_then(|v| v.as_array()) {
+                    Some(fields) => fields_size(fields, generated_types)?,
+                    None => 0,
+                };
+                match size {
+                    None => size = Some(variant_size),
+                    Some(s) if s != variant_size => return None,
+                    _ => {}
+                }
+            }
+            Some(1 + size.unwrap_or(0))
+        }
+        _ => None,
+    }
+}
+
+/// Sums the sizes of a list of fields. Fields are either named (`{ "name", "type" }`) or,
+/// for tuple enum variants, bare types.
+pub fn fields_size(
+    fields: &[Value],
+    generated_types: &HashMap<String, Option<usize>>,
+) -> Option<usize> {
+    fields
+        .iter()
+        .map(|field| map_idl_type(field.get("type").unwrap_or(field), generated_types).size)
+        .sum()
+}
+
+/// Returns the byte offset of each field relative to the start of the struct. Offsets are only
+/// known up to and including the first variable-size field; every field after it gets `None`.
+pub fn field_offsets(
+    fields: &[Value],
+    generated_types: &HashMap<String, Option<usize>>,
+) -> Vec<Option<usize>> {
+    let mut offset = Some(0);
+    fields
+        .iter()
+        .map(|field| {
+            let current = offset;
+            offset = match (offset, field.get("type")) {
+                (Some(o), Some(ty)) => map_idl_type(ty, generated_types).size.map(|s| o + s),
+                _ => None,
+            };
+            current
+        })
+        .collect()
+}
+
+/// Generates the layout constants for a struct with the given fields: `IS_FIXED_SIZE`, `LEN`
+/// when the struct is fixed-size, and `<FIELD>_OFFSET` for every field at a static offset.
+pub fn layout_consts(
+    fields: &[Value],
+    generated_types: &HashMap<String, Option<usize>>,
+) -> proc_macro2::TokenStream {
+    let size = fields_size(fields, generated_types);
+    let mut consts = vec![size_consts(size)];
+
+    for (field, offset) in fields.iter().zip(field_offsets(fields, generated_types)) {
+        let (Some(field_name), Some(offset)) = (field.get("name").and_then(|v| v.as_str()), offset)
+        else {
+            continue;
+        };
+        let const_ident = syn::Ident::new(
+            &format!("{}_OFFSET", to_snake_case(field_name).to_uppercase()),
+            proc_macro2::Span::call_site(),
+        );
+        let doc = format!(" Byte offset of `{}` within the serialized struct", field_name);
+        consts.push(quote::quote! {
+            #[doc = #doc]
+            pub const #const_ident: usize = #offset;
+        });
+    }
+
+    quote::quote! { #( #consts )* }
+}
+
+/// Generates `IS_FIXED_SIZE`, plus `LEN` when the size is known statically. Variable-size types
+/// have no `LEN`, so using it on them is a compile error.
+pub fn size_consts(size: Option<usize>) -> proc_macro2::TokenStream {
+    match size {
+        Some(len) => quote::quote! {
+            /// Whether this type always serializes to the same number of bytes
+            pub const IS_FIXED_SIZE: bool = true;
+            /// Serialized size in bytes
+            pub const LEN: usize = #len;
+        },
+        None => quote::quote! {
+            /// Whether this type always serializes to the same number of bytes
+            pub const IS_FIXED_SIZE: bool = false;
+        },
+    }
+}
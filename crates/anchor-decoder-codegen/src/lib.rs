@@ -0,0 +1,1639 @@
+//! Code generation behind the `anchor_idl` macro. Use [`generate_to_file`] from a build script
+//! to write the generated decoder to a source file instead of expanding it with the macro.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::Path;
+
+use quote::quote;
+use serde_json::Value;
+
+mod diff;
+mod builder;
+mod defaults;
+mod discriminator;
+mod docs;
+mod idl;
+mod layout;
+mod metadata;
+mod names;
+mod options;
+mod pda;
+mod summary;
+mod validate;
+mod versions;
+mod view;
+
+pub use diff::{diff_idls, IdlChange, Impact};
+use builder::instruction_builder;
+use defaults::{enum_default, struct_default};
+use discriminator::{dispatch, parse_discriminator};
+pub use discriminator::EMIT_CPI_INSTRUCTION_DISCRIMINATOR;
+use docs::doc_attrs;
+use idl::{account_const, account_field, flat_accounts};
+pub use idl::account_names;
+pub use docs::program_doc_lines;
+use layout::{field_offsets, layout_consts, size_consts, type_def_size, type_sizes};
+pub use metadata::idl_hash;
+use metadata::metadata_consts;
+use names::GeneratedNames;
+use options::{excluded_types, type_kinds, Kind};
+use pda::instruction_pdas;
+use summary::{decoded_summary, enum_summary, struct_summary, variant_summary_arm};
+pub use options::{Filter, ItemOptions, Options};
+pub use validate::{validate_idl, IdlError};
+pub use versions::{generate_versions, IdlVersion};
+use view::account_view;
+
+/// Helper to convert snake_case to CamelCase (e.g. "create_order" -> "CreateOrder")
+fn to_camel_case(s: &str) -> String {
+    s.split('_')
+        .map(|word| {
+            let mut c = word.chars();
+            match c.next() {
+                None => String::new(),
+                Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
+            }
+        })
+        .collect()
+}
+
+/// Helper to convert camelCase or CamelCase to snake_case (e.g. "feeRecipient" ->
+/// "fee_recipient", "NFTMint" -> "nft_mint"). snake_case names are returned unchanged.
+fn to_snake_case(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut snake = String::with_capacity(s.len());
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// A Rust type mapped from an IDL type, along with its Borsh-serialized size in bytes if that
+/// size is known statically.
+struct MappedType {
+    tokens: proc_macro2::TokenStream,
+    size: Option<usize>,
+}
+
+/// Maps an IDL type (which can be a string like "u8" or an object for arrays or defined types)
+/// into the corresponding Rust type as tokens. The `generated_types` map contains the names
+/// of custom types that will be generated by this macro, along with their static sizes.
+///
+/// The size is `None` for types whose encoding depends on the data (`string`, `bytes`, `vec`,
+/// `option`, variable-size defined types) and for unsupported types.
+fn map_idl_type(arg_type: &Value, generated_types: &HashMap<String, Option<usize>>) -> MappedType {
+    let fixed = |tokens, size| MappedType { tokens, size: Some(size) };
+    let variable = |tokens| MappedType { tokens, size: None };
+
+    if let Some(s) = arg_type.as_str() {
+        match s {
+            "u8" => fixed(quote! { u8 }, 1),
+            "u16" => fixed(quote! { u16 }, 2),
+            "u32" => fixed(quote! { u32 }, 4),
+            "u64" => fixed(quote! { u64 }, 8),
+            "u128" => fixed(quote! { u128 }, 16),
+            "i8" => fixed(quote! { i8 }, 1),
+            "i16" => fixed(quote! { i16 }, 2),
+            "i32" => fixed(quote! { i32 }, 4),
+            "i64" => fixed(quote! { i64 }, 8),
+            "i128" => fixed(quote! { i128 }, 16),
+            "f32" => fixed(quote! { f32 }, 4),
+            "f64" => fixed(quote! { f64 }, 8),
+            "bool" => fixed(quote! { bool }, 1),
+            "pubkey" => fixed(quote! { ::solana_sdk::pubkey::Pubkey }, 32),
+            "string" => variable(quote! { String }),
+            "bytes" => variable(quote! { Vec<u8> }),
+            _ => variable(quote! { () }), // fallback for unsupported types
+        }
+    } else if let Some(obj) = arg_type.as_object() {
+        if let Some(array_val) = obj.get("array") {
+            if let Some(arr) = array_val.as_array() {
+                if arr.len() == 2 {
+                    let inner = map_idl_type(&arr[0], generated_types);
+                    if let Some(len) = arr[1].as_u64() {
+                        let inner_tokens = inner.tokens;
+                        let len_literal =
+                            syn::LitInt::new(&len.to_string(), proc_macro2::Span::call_site());
+                        return MappedType {
+                            tokens: quote! { [#inner_tokens; #len_literal] },
+                            size: inner.size.map(|size| size * len as usize),
+                        };
+                    }
+                }
+            }
+        } else if let Some(inner) = obj.get("vec") {
+            let inner = map_idl_type(inner, generated_types).tokens;
+            return variable(quote! { Vec<#inner> });
+        } else if let Some(inner) = obj.get("option") {
+            let inner = map_idl_type(inner, generated_types).tokens;
+            return variable(quote! { Option<#inner> });
+        } else if let Some(defined) = obj.get("defined") {
+            if let Some(defined_obj) = defined.as_object() {
+                if let Some(name) = defined_obj.get("name").and_then(|n| n.as_str()) {
+                    let type_ident = syn::Ident::new(name, proc_macro2::Span::call_site());
+                    // If the type was generated by our macro, reference it directly.
+                    // Otherwise assume it's external (and qualify it).
+                    if let Some(size) = generated_types.get(name) {
+                        return MappedType {
+                            tokens: quote! { #type_ident },
+                            size: *size,
+                        };
+                    } else {
+                        return variable(quote! { ::crate::#type_ident });
+                    }
+                }
+            }
+        }
+        variable(quote! { () })
+    } else {
+        variable(quote! { () })
+    }
+}
+
+/// Generates the `IdlAccountMeta` of an instruction account. Reads both the current `signer` and
+/// `writable` flags and the legacy `isSigner` and `isMut` ones. Relations of an account in a
+/// composite group name its siblings, so they're qualified with the group like the account.
+fn account_meta(name: &str, account: &Value) -> proc_macro2::TokenStream {
+    let flag = |keys: &[&str]| {
+        keys.iter()
+            .any(|key| account.get(*key).and_then(|v| v.as_bool()).unwrap_or(false))
+    };
+    let is_signer = flag(&["signer", "isSigner"]);
+    let is_writable = flag(&["writable", "isMut"]);
+    let is_optional = flag(&["optional", "isOptional"]);
+    let fixed_address = match account.get("address").and_then(|v| v.as_str()) {
+        Some(address) => quote! { Some(::solana_sdk::pubkey!(#address)) },
+        None => quote! { None },
+    };
+    let group = name.rfind('.').map_or("", |dot| &name[..=dot]);
+    let relations = account
+        .get("relations")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .map(|related| format!("{}{}", group, related));
+    quote! {
+        ::anchor_decoder_runtime::IdlAccountMeta {
+            name: #name,
+            is_signer: #is_signer,
+            is_writable: #is_writable,
+            is_optional: #is_optional,
+            fixed_address: #fixed_address,
+            relations: &[ #( #relations ),* ],
+        }
+    }
+}
+
+/// Whether a type definition is a struct, which gets a `summarize_fields` method
+fn is_struct(type_def: Option<&Value>) -> bool {
+    type_def
+        .and_then(|t| t.get("type"))
+        .and_then(|t| t.get("kind"))
+        .and_then(|v| v.as_str())
+        == Some("struct")
+}
+
+/// Generates the Rust type for an IDL `types` entry, along with its layout constants and a
+/// `decode` helper. Returns `None` for entries this crate doesn't know how to generate.
+fn type_def_tokens(
+    type_def: &Value,
+    generated_types: &HashMap<String, Option<usize>>,
+    kinds: &[Kind],
+    options: &Options,
+) -> Option<proc_macro2::TokenStream> {
+    if let (Some(name), Some(type_info)) = (
+        type_def.get("name").and_then(|v| v.as_str()),
+        type_def.get("type").and_then(|v| v.as_object()),
+    ) {
+        let type_ident = syn::Ident::new(name, proc_macro2::Span::call_site());
+        let type_docs = doc_attrs(type_def);
+
+        // Check the kind of the type.
+        if let Some(kind) = type_info.get("kind").and_then(|v| v.as_str()) {
+            match kind {
+                "struct" => {
+                    // Process struct definitions.
+                    let field_defs = if let Some(fields) = type_info.get("fields").and_then(|v| v.as_array()) {
+                        let mut field_defs = Vec::new();
+                        for field in fields {
+                            if let (Some(field_name), Some(field_type)) = (
+                                field.get("name").and_then(|v| v.as_str()),
+                                field.get("type"),
+                            ) {
+                                let field_ident = syn::Ident::new(
+                                    field_name,
+                                    proc_macro2::Span::call_site(),
+                                );
+                                let field_type = map_idl_type(field_type, generated_types).tokens;
+                                let field_docs = doc_attrs(field);
+                                field_defs.push(quote! {
+                                    #field_docs
+                                    pub #field_ident: #field_type,
+                                });
+                            }
+                        }
+                        field_defs
+                    } else {
+                        // Handle empty struct (no fields property)
+                        Vec::new()
+                    };
+                    
+                    let layout = match type_info.get("fields").and_then(|v| v.as_array()) {
+                        Some(fields) => layout_consts(fields, generated_types),
+                        // Without `fields`, the IDL doesn't describe the layout.
+                        None => size_consts(None),
+                    };
+
+                    let summary = struct_summary(
+                        &type_ident,
+                        type_info
+                            .get("fields")
+                            .and_then(|v| v.as_array())
+                            .map(Vec::as_slice)
+                            .unwrap_or_default(),
+                        true,
+                    );
+                    let extra_attrs = options.attrs(kinds);
+                    let default = options.derives_default(kinds).then(|| {
+                        struct_default(
+                            &type_ident,
+                            type_info
+                                .get("fields")
+                                .and_then(|v| v.as_array())
+                                .map(Vec::as_slice)
+                                .unwrap_or_default(),
+                        )
+                    });
+                    return Some(quote! {
+                        #type_docs
+                        #[derive(Debug, ::borsh::BorshSerialize, ::borsh::BorshDeserialize)]
+                        #extra_attrs
+                        pub struct #type_ident {
+                            #( #field_defs )*
+                        }
+                        impl #type_ident {
+                            #layout
+
+                            pub fn decode(data: &[u8]) -> Result<Self, ::std::io::Error> {
+                                <Self as ::borsh::BorshDeserialize>::try_from_slice(data)
+                            }
+                        }
+
+                        #summary
+                        #default
+                    });
+                }
+                "enum" => {
+                    // Process enum definitions.
+                    if let Some(variants) =
+                        type_info.get("variants").and_then(|v| v.as_array())
+                    {
+                        let mut variant_tokens = Vec::new();
+                        for variant in variants {
+                            if let Some(variant_name) =
+                                variant.get("name").and_then(|v| v.as_str())
+                            {
+                                let variant_ident = syn::Ident::new(
+                                    variant_name,
+                                    proc_macro2::Span::call_site(),
+                                );
+                                let variant_docs = doc_attrs(variant);
+                                // Variants either carry named fields, a tuple of
+                                // bare types, or nothing at all.
+                                let fields = variant
+                                    .get("fields")
+                                    .and_then(|v| v.as_array())
+                                    .filter(|fields| !fields.is_empty());
+                                match fields {
+                                    Some(fields) if fields[0].get("name").is_some() => {
+                                        let named = fields.iter().filter_map(|field| {
+                                            let field_ident = syn::Ident::new(
+                                                field.get("name")?.as_str()?,
+                                                proc_macro2::Span::call_site(),
+                                            );
+                                            let field_type = map_idl_type(
+                                                field.get("type")?,
+                                                generated_types,
+                                            )
+                                            .tokens;
+                                            let field_docs = doc_attrs(field);
+                                            Some(quote! { #field_docs #field_ident: #field_type })
+                                        });
+                                        variant_tokens.push(quote! {
+                                            #variant_docs
+                                            #variant_ident { #( #named ),* },
+                                        });
+                                    }
+                                    Some(fields) => {
+                                        let tuple = fields.iter().map(|field| {
+                                            map_idl_type(field, generated_types).tokens
+                                        });
+                                        variant_tokens.push(quote! {
+                                            #variant_docs
+                                            #variant_ident( #( #tuple ),* ),
+                                        });
+                                    }
+                                    None => variant_tokens.push(quote! {
+                                        #variant_docs
+                                        #variant_ident,
+                                    }),
+                                }
+                            }
+                        }
+                        let layout = size_consts(generated_types[name]);
+                        let summary = enum_summary(&type_ident, variants);
+                        let extra_attrs = options.attrs(kinds);
+                        let default = options
+                            .derives_default(kinds)
+                            .then(|| enum_default(&type_ident, variants));
+                        return Some(quote! {
+                            #type_docs
+                            #[derive(Debug, ::borsh::BorshSerialize, ::borsh::BorshDeserialize)]
+                            #extra_attrs
+                            pub enum #type_ident {
+                                #( #variant_tokens )*
+                            }
+                            impl #type_ident {
+                                #layout
+
+                                pub fn decode(data: &[u8]) -> Result<Self, ::std::io::Error> {
+                                    <Self as ::borsh::BorshDeserialize>::try_from_slice(data)
+                                }
+                            }
+
+                            #summary
+                            #default
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+/// Generates the decoder for an Anchor IDL: the same items that `#[anchor_idl]` expands to.
+///
+/// For each instruction:
+///  - It generates a struct for the instruction's arguments (if any), with a constant discriminator.
+///  - It creates an enum variant for the instruction.
+///  - It produces a helper function (`decode_instruction`) to match and decode incoming data.
+///
+/// For each account:
+///  - It assumes the account type is defined under "types" (by matching name).
+///  - It uses the provided discriminator to generate a match arm that decodes the account data,
+///    skipping the discriminator.
+///
+/// A `Decoder` unit struct (`AnchorDecoder` if an IDL type is named `Decoder`) implements
+/// `anchor_decoder_runtime::ProgramDecoder`, so the crate using the output must also depend on
+/// `anchor-decoder-runtime`.
+///
+/// The output doesn't define the program `ID` it checks program and owner addresses against;
+/// the caller declares it next to the generated items, typically as `IDL_ADDRESS`. Every path
+/// in the output is fully qualified, so nothing is imported into the surrounding module.
+///
+/// Discriminators may be any non-zero length (Anchor defaults to 8 bytes). The IDL is checked
+/// with [`validate_idl`] first; if it's malformed, this panics listing every problem found.
+pub fn generate(idl: &Value) -> proc_macro2::TokenStream {
+    generate_with(idl, &Options::default())
+}
+
+/// Like [`generate`], with extra derives and attributes on the generated types (see [`Options`])
+pub fn generate_with(idl: &Value, options: &Options) -> proc_macro2::TokenStream {
+    generate_version(idl, options, &HashSet::new())
+}
+
+/// Generates the decoder for one IDL. Types named in `shared_types` are re-exported from the
+/// parent module instead of generated.
+fn generate_version(
+    idl: &Value,
+    options: &Options,
+    shared_types: &HashSet<String>,
+) -> proc_macro2::TokenStream {
+    if let Err(errors) = validate_idl(idl) {
+        let errors: Vec<String> = errors.iter().map(|e| format!("  - {}", e)).collect();
+        panic!("Invalid IDL:\n{}", errors.join("\n"));
+    }
+
+    // Collect the names of all types defined in the IDL, along with their static sizes.
+    let generated_types: HashMap<String, Option<usize>> =
+        if let Some(types) = idl.get("types").and_then(|v| v.as_array()) {
+            type_sizes(types)
+        } else {
+            HashMap::new()
+        };
+
+    // Index the type definitions by name so the layouts of account types can be resolved.
+    let type_defs: HashMap<&str, &Value> =
+        if let Some(types) = idl.get("types").and_then(|v| v.as_array()) {
+            types
+                .iter()
+                .filter_map(|t| t.get("name").and_then(|v| v.as_str()).map(|name| (name, t)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+    // Which options apply to each type depends on where it is used.
+    let type_kinds = type_kinds(idl);
+    let names = GeneratedNames::new(idl);
+    let decoder = names.support("Decoder");
+    let decoded_instruction = names.support("DecodedInstruction");
+    let decoded_transaction_instruction = names.support("DecodedTransactionInstruction");
+    let decoded_account = names.support("DecodedAccount");
+    let decoded_event = names.support("DecodedEvent");
+    let account_decode_error = names.support("AccountDecodeError");
+    let account_filter = names.support("AccountFilter");
+    let lookup_table_error = names.support("LookupTableError");
+    let excluded_types = excluded_types(idl, options);
+
+    let mut struct_defs = Vec::new();
+
+    // Process custom type definitions. Types shared between IDL versions are generated once, in
+    // the parent module, and re-exported.
+    if let Some(types) = idl.get("types").and_then(|v| v.as_array()) {
+        for type_def in types {
+            let Some(name) = type_def.get("name").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            if excluded_types.contains(name) {
+                continue;
+            }
+            if shared_types.contains(name) {
+                let type_ident = syn::Ident::new(name, proc_macro2::Span::call_site());
+                struct_defs.push(quote! { pub use super::#type_ident; });
+                continue;
+            }
+            let kinds = type_kinds.get(name).map(Vec::as_slice).unwrap_or_default();
+            struct_defs.extend(type_def_tokens(type_def, &generated_types, kinds, options));
+        }
+    }
+
+    let instructions = idl
+        .get("instructions")
+        .and_then(|v| v.as_array())
+        .expect("IDL JSON does not contain an 'instructions' array");
+
+    let mut enum_variants = Vec::new();
+    let mut match_arms = Vec::new();
+    let mut name_arms = Vec::new();
+    let mut discriminator_entries = Vec::new();
+    let mut variant_name_arms = Vec::new();
+    let mut variant_account_index_arms = Vec::new();
+    let mut variant_map_accounts_arms = Vec::new();
+    let mut variant_account_metas_arms = Vec::new();
+    let mut variant_verify_pdas_arms = Vec::new();
+    let mut variant_summary_arms = Vec::new();
+    let mut any_pda_checks = false;
+    let mut uses_account_data = false;
+    let mut ignores_instructions = false;
+
+    for inst in instructions {
+        // Get instruction name, discriminator, and args.
+        let name = inst.get("name").and_then(|v| v.as_str()).unwrap();
+        let disc_values = parse_discriminator(inst, "instruction", name);
+        let args = inst
+            .get("args")
+            .and_then(|v| v.as_array())
+            .expect("Args missing or not an array");
+
+        let instruction_docs = doc_attrs(inst);
+        let args_attrs = options.attrs(&[Kind::Args]);
+
+        // The variant is the instruction name in CamelCase. The structs usually share it, but
+        // fall back to a longer name if it's taken by an IDL type.
+        let variant = syn::Ident::new(&to_camel_case(name), proc_macro2::Span::call_site());
+        let (struct_name, accounts_struct_name, keys_struct_name) = names.instruction(name);
+
+        // Generate a constant for the discriminator.
+        let disc_len = disc_values.len();
+        let disc_tokens = quote! { [ #( #disc_values ),* ] };
+
+        name_arms.push((disc_values.clone(), quote! { return Some(#name); }));
+        discriminator_entries.push(quote! { (#name, &#disc_tokens) });
+
+        // Left out instructions are still dispatched, so they decode as ignored rather than
+        // unknown.
+        if !options.instruction_filter.matches(name) {
+            match_arms.push((disc_values, quote! {
+                return Some(#decoded_instruction::Ignored(#name));
+            }));
+            ignores_instructions = true;
+            continue;
+        }
+
+        let pdas = instruction_pdas(inst, &accounts_struct_name, &type_defs);
+        struct_defs.extend(instruction_builder(
+            inst,
+            &struct_name,
+            &keys_struct_name,
+            &accounts_struct_name,
+            &pdas,
+        ));
+        let find_fns = &pdas.find_fns;
+        let pda_checks = &pdas.checks;
+        any_pda_checks |= !pda_checks.is_empty();
+        uses_account_data |= pdas.uses_account_data;
+
+        // Process accounts for this instruction
+        let mut account_consts = Vec::new();
+        let mut account_fields = Vec::new();
+        let mut account_indices = Vec::new();
+        let mut account_name_matches = Vec::new();
+        let mut account_tuples = Vec::new();
+        let mut account_index_matches = Vec::new();
+        let mut account_metas = Vec::new();
+
+        if inst.get("accounts").and_then(|v| v.as_array()).is_some() {
+            // Composite account groups are flattened, so indices match the instruction's keys.
+            for (idx, (account_name, account)) in flat_accounts(inst).into_iter().enumerate() {
+                let const_ident = account_const(&account_name);
+                let idx_lit = syn::LitInt::new(&idx.to_string(), proc_macro2::Span::call_site());
+
+                account_consts.push(quote! {
+                    pub const #const_ident: usize = #idx_lit;
+                });
+
+                let field_ident = account_field(&account_name);
+                let account_docs = doc_attrs(account);
+                account_fields.push(quote! {
+                    #account_docs
+                    pub #field_ident: usize,
+                });
+
+                account_indices.push(quote! {
+                    #field_ident: #idx_lit,
+                });
+
+                // Create match arm for get_account_name
+                account_name_matches.push(quote! {
+                    #idx_lit => Some(#account_name),
+                });
+
+                // Create tuple for get_all_accounts
+                account_tuples.push(quote! {
+                    (#account_name, Self::#const_ident)
+                });
+
+                // Create match arm for get_account_index
+                account_index_matches.push(quote! {
+                    #account_name => Some(Self::#const_ident),
+                });
+
+                account_metas.push(account_meta(&account_name, account));
+            }
+
+            // Generate the accounts struct
+            struct_defs.push(quote! {
+                #[derive(Debug, Clone, Copy)]
+                pub struct #accounts_struct_name {
+                    #( #account_fields )*
+                }
+
+                impl #accounts_struct_name {
+                    #( #account_consts )*
+
+                    /// Static metadata of each account from the IDL, by account index
+                    pub const METAS: &'static [::anchor_decoder_runtime::IdlAccountMeta] = &[
+                        #( #account_metas, )*
+                    ];
+
+                    pub const fn new() -> Self {
+                        Self {
+                            #( #account_indices )*
+                        }
+                    }
+
+                    pub fn get_account_name(&self, index: usize) -> Option<&'static str> {
+                        match index {
+                            #( #account_name_matches )*
+                            _ => None,
+                        }
+                    }
+
+                    pub fn get_all_accounts(&self) -> &'static [(&'static str, usize)] {
+                        &[
+                            #( #account_tuples, )*
+                        ]
+                    }
+                    
+                    pub fn get_account_index(&self, name: &str) -> Option<usize> {
+                        match name {
+                            #( #account_index_matches )*
+                            _ => None,
+                        }
+                    }
+
+                    /// Returns the IDL metadata of the account at `index`
+                    pub fn get_account_meta(&self, index: usize) -> Option<&'static ::anchor_decoder_runtime::IdlAccountMeta> {
+                        Self::METAS.get(index)
+                    }
+
+                    #( #find_fns )*
+                }
+            });
+        }
+
+        if !args.is_empty() {
+            // Generate struct fields by mapping each argument's type.
+            let mut fields = Vec::new();
+            for arg in args {
+                let arg_name = arg.get("name").and_then(|v| v.as_str()).unwrap();
+                let arg_type = arg.get("type").expect("Missing type in argument");
+                let field_ident = syn::Ident::new(arg_name, proc_macro2::Span::call_site());
+                let field_type = map_idl_type(arg_type, &generated_types).tokens;
+                let arg_docs = doc_attrs(arg);
+                fields.push(quote! {
+                    #arg_docs
+                    pub #field_ident: #field_type,
+                });
+            }
+
+            let layout = layout_consts(args, &generated_types);
+            let summary = struct_summary(&struct_name, args, false);
+            let default = options
+                .derives_default(&[Kind::Args])
+                .then(|| struct_default(&struct_name, args));
+
+            struct_defs.push(quote! {
+                #instruction_docs
+                #[derive(Debug, ::borsh::BorshSerialize, ::borsh::BorshDeserialize)]
+                #args_attrs
+                pub struct #struct_name {
+                    #( #fields )*
+                }
+                impl #struct_name {
+                    pub const DISCRIMINATOR: [u8; #disc_len] = #disc_tokens;
+                    pub const ACCOUNTS: #accounts_struct_name = #accounts_struct_name::new();
+                    #layout
+                    
+                    pub fn decode(data: &[u8]) -> Result<Self, ::std::io::Error> {
+                        // Skip the discriminator
+                        let payload = &data[Self::DISCRIMINATOR.len()..];
+                        <Self as ::borsh::BorshDeserialize>::try_from_slice(payload)
+                    }
+                    
+                    /// Maps account indices to their semantic names
+                    pub fn map_accounts<'a>(accounts: &'a [::solana_sdk::pubkey::Pubkey]) -> ::std::collections::HashMap<&'static str, &'a ::solana_sdk::pubkey::Pubkey> {
+                        let mut result = ::std::collections::HashMap::new();
+                        for (i, account) in accounts.iter().enumerate() {
+                            if let Some(name) = Self::ACCOUNTS.get_account_name(i) {
+                                result.insert(name, account);
+                            }
+                        }
+                        result
+                    }
+                }
+
+                #summary
+                #default
+            });
+
+            enum_variants.push(quote! {
+                #instruction_docs
+                #variant(#struct_name)
+            });
+            variant_name_arms.push(quote! { #decoded_instruction::#variant(_) => #name });
+            variant_account_index_arms.push(quote! {
+                #decoded_instruction::#variant(_) => #struct_name::ACCOUNTS.get_account_index(name)
+            });
+            variant_map_accounts_arms.push(quote! {
+                #decoded_instruction::#variant(_) => #struct_name::map_accounts(accounts)
+            });
+            variant_account_metas_arms.push(quote! {
+                #decoded_instruction::#variant(_) => #accounts_struct_name::METAS
+            });
+            let args_pattern = if pdas.uses_args {
+                quote! { args }
+            } else {
+                quote! { _ }
+            };
+            variant_verify_pdas_arms.push(quote! {
+                #decoded_instruction::#variant(#args_pattern) => { #( #pda_checks )* }
+            });
+            variant_summary_arms.push(variant_summary_arm(
+                quote! { #decoded_instruction::#variant(value) },
+                name,
+                true,
+            ));
+            match_arms.push((disc_values, quote! {
+                return #struct_name::decode(data).ok().map(#decoded_instruction::#variant);
+            }));
+        } else {
+            // For instructions with no arguments, generate a unit struct.
+            struct_defs.push(quote! {
+                #instruction_docs
+                #[derive(Debug)]
+                #args_attrs
+                pub struct #struct_name;
+                impl #struct_name {
+                    pub const DISCRIMINATOR: [u8; #disc_len] = #disc_tokens;
+                    pub const ACCOUNTS: #accounts_struct_name = #accounts_struct_name::new();
+                    
+                    /// Maps account indices to their semantic names
+                    pub fn map_accounts<'a>(accounts: &'a [::solana_sdk::pubkey::Pubkey]) -> ::std::collections::HashMap<&'static str, &'a ::solana_sdk::pubkey::Pubkey> {
+                        let mut result = ::std::collections::HashMap::new();
+                        for (i, account) in accounts.iter().enumerate() {
+                            if let Some(name) = Self::ACCOUNTS.get_account_name(i) {
+                                result.insert(name, account);
+                            }
+                        }
+                        result
+                    }
+                }
+            });
+            enum_variants.push(quote! {
+                #instruction_docs
+                #variant
+            });
+            variant_name_arms.push(quote! { #decoded_instruction::#variant => #name });
+            variant_account_index_arms.push(quote! {
+                #decoded_instruction::#variant => #struct_name::ACCOUNTS.get_account_index(name)
+            });
+            variant_map_accounts_arms.push(quote! {
+                #decoded_instruction::#variant => #struct_name::map_accounts(accounts)
+            });
+            variant_account_metas_arms.push(quote! {
+                #decoded_instruction::#variant => #accounts_struct_name::METAS
+            });
+            variant_verify_pdas_arms.push(quote! {
+                #decoded_instruction::#variant => { #( #pda_checks )* }
+            });
+            let summary = format!("{}()", name);
+            variant_summary_arms.push(quote! {
+                #decoded_instruction::#variant => out.write_str(#summary)
+            });
+            match_arms.push((disc_values, quote! {
+                return Some(#decoded_instruction::#variant);
+            }));
+        }
+    }
+
+    // Process accounts from the IDL.
+    let mut account_enum_variants = Vec::new();
+    let mut account_summary_arms = Vec::new();
+    let mut pubkey_field_arms = Vec::new();
+    let mut account_match_arms = Vec::new();
+    let mut account_discriminator_entries = Vec::new();
+    let mut ignores_accounts = false;
+    if let Some(accounts) = idl.get("accounts").and_then(|v| v.as_array()) {
+        for account in accounts {
+            let name = account.get("name").and_then(|v| v.as_str()).unwrap();
+            let disc_values = parse_discriminator(account, "account", name);
+            let type_ident = syn::Ident::new(name, proc_macro2::Span::call_site());
+            let disc_len = disc_values.len();
+            let disc_tokens = quote! { [ #( #disc_values ),* ] };
+            account_discriminator_entries.push(quote! { (#name, &#disc_tokens) });
+
+            if !options.account_filter.matches(name) {
+                account_match_arms.push((disc_values, quote! {
+                    return Ok(#decoded_account::Ignored(#name));
+                }));
+                ignores_accounts = true;
+                continue;
+            }
+
+            account_enum_variants.push(quote! {
+                #type_ident(#type_ident)
+            });
+            account_summary_arms.push(variant_summary_arm(
+                quote! { #decoded_account::#type_ident(value) },
+                name,
+                is_struct(type_defs.get(name).copied()),
+            ));
+            // Accounts are often allocated with more space than they serialize to, so bytes past
+            // the end of the account are ignored.
+            account_match_arms.push((disc_values, quote! {
+                return <#type_ident as ::borsh::BorshDeserialize>::deserialize(&mut &data[#disc_len..])
+                    .map(#decoded_account::#type_ident)
+                    .map_err(#account_decode_error::InvalidData);
+            }));
+
+            // Build getProgramAccounts filters from the account's layout. The data size filter is
+            // only emitted for fixed-size accounts, and field filters only for fields whose offset
+            // does not depend on a preceding variable-size field.
+            let type_def = type_defs.get(name).copied();
+            let mut filter_fns = Vec::new();
+            // Accounts are often allocated with more space than they serialize to, so the data
+            // size filter is opt-in rather than part of `filters()`.
+            let mut space = None;
+            if let Some(size) = type_def.and_then(|t| type_def_size(t, &generated_types)) {
+                let data_size = (disc_len + size) as u64;
+                space = Some(quote! {
+                    /// Minimum size of the account data: the discriminator plus the serialized
+                    /// account. Accounts may be allocated with more space than this.
+                    pub const SPACE: usize = Self::DISCRIMINATOR.len() + Self::LEN;
+
+                    /// Filter matching accounts whose data is exactly `SPACE` bytes long. Only
+                    /// use it for accounts known to be allocated with exactly their serialized
+                    /// size; many are allocated with extra space and wouldn't match.
+                    pub fn data_size_filter() -> #account_filter {
+                        #account_filter::DataSize(#data_size)
+                    }
+                });
+            }
+            if let Some(fields) = type_def
+                .and_then(|t| t.get("type"))
+                .and_then(|t| t.get("fields"))
+                .and_then(|v| v.as_array())
+            {
+                struct_defs.push(account_view(
+                    &type_ident,
+                    &names.view(name),
+                    &account_decode_error,
+                    fields,
+                    &generated_types,
+                ));
+
+                // Address fields are what `relations` constraints compare against.
+                for field in fields {
+                    if field.get("type").and_then(|v| v.as_str()) != Some("pubkey") {
+                        continue;
+                    }
+                    let Some(field_name) = field.get("name").and_then(|v| v.as_str()) else {
+                        continue;
+                    };
+                    let field_ident = syn::Ident::new(field_name, proc_macro2::Span::call_site());
+                    pubkey_field_arms.push(quote! {
+                        (#decoded_account::#type_ident(account), #field_name) => Some(account.#field_ident)
+                    });
+                }
+
+                for (field, offset) in fields.iter().zip(field_offsets(fields, &generated_types)) {
+                    let (Some(field_name), Some(field_type), Some(offset)) = (
+                        field.get("name").and_then(|v| v.as_str()),
+                        field.get("type"),
+                        offset,
+                    ) else {
+                        continue;
+                    };
+                    let fn_ident = syn::Ident::new(
+                        &format!("filter_by_{}", to_snake_case(field_name)),
+                        proc_macro2::Span::call_site(),
+                    );
+                    let field_type = map_idl_type(field_type, &generated_types).tokens;
+                    let offset = disc_len + offset;
+                    let doc = format!(
+                        " Filter matching accounts whose `{}` field equals `value` (offset {})",
+                        field_name, offset
+                    );
+                    filter_fns.push(quote! {
+                        #[doc = #doc]
+                        pub fn #fn_ident(value: &#field_type) -> #account_filter {
+                            #account_filter::Memcmp {
+                                offset: #offset,
+                                bytes: ::borsh::BorshSerialize::try_to_vec(value)
+                                    .expect("serializing into a Vec cannot fail"),
+                            }
+                        }
+                    });
+                }
+            }
+
+            struct_defs.push(quote! {
+                impl #type_ident {
+                    pub const DISCRIMINATOR: [u8; #disc_len] = #disc_tokens;
+                    #space
+
+                    /// Filter matching accounts whose data starts with this account's discriminator
+                    pub fn discriminator_filter() -> #account_filter {
+                        #account_filter::Memcmp {
+                            offset: 0,
+                            bytes: Self::DISCRIMINATOR.to_vec(),
+                        }
+                    }
+
+                    /// Filters selecting every account of this type with `getProgramAccounts`
+                    pub fn filters() -> Vec<#account_filter> {
+                        vec![Self::discriminator_filter()]
+                    }
+
+                    #( #filter_fns )*
+
+                    /// Decodes the account after checking that it is owned by this program and
+                    /// that its data starts with this account's discriminator. Bytes past the end
+                    /// of the account, as in accounts allocated with extra space, are ignored.
+                    pub fn decode_account_info(owner: &::solana_sdk::pubkey::Pubkey, data: &[u8]) -> Result<Self, #account_decode_error> {
+                        __anchor_decoder::check_account_owner(owner)?;
+                        let Some(mut payload) = data.strip_prefix(&Self::DISCRIMINATOR[..]) else {
+                            return Err(#account_decode_error::UnknownDiscriminator);
+                        };
+                        <Self as ::borsh::BorshDeserialize>::deserialize(&mut payload)
+                            .map_err(#account_decode_error::InvalidData)
+                    }
+                }
+            });
+        }
+    }
+
+    // Process events from the IDL.
+    let mut event_enum_variants = Vec::new();
+    let mut event_summary_arms = Vec::new();
+    let mut event_match_arms = Vec::new();
+    let mut event_discriminator_entries = Vec::new();
+    let mut ignores_events = false;
+    if let Some(events) = idl.get("events").and_then(|v| v.as_array()) {
+        for event in events {
+            let name = event.get("name").and_then(|v| v.as_str()).unwrap();
+            let disc_values = parse_discriminator(event, "event", name);
+            let type_ident = syn::Ident::new(name, proc_macro2::Span::call_site());
+            let disc_len = disc_values.len();
+            let disc_tokens = quote! { [ #( #disc_values ),* ] };
+            event_discriminator_entries.push(quote! { (#name, &#disc_tokens) });
+
+            if !options.event_filter.matches(name) {
+                event_match_arms.push((disc_values, quote! {
+                    return Some(#decoded_event::Ignored(#name));
+                }));
+                ignores_events = true;
+                continue;
+            }
+
+            event_enum_variants.push(quote! {
+                #type_ident(#type_ident)
+            });
+            event_summary_arms.push(variant_summary_arm(
+                quote! { #decoded_event::#type_ident(value) },
+                name,
+                is_struct(type_defs.get(name).copied()),
+            ));
+            event_match_arms.push((disc_values, quote! {
+                return #type_ident::decode(&data[#disc_len..]).ok().map(#decoded_event::#type_ident);
+            }));
+        }
+    }
+
+    let program_address = idl
+        .get("address")
+        .and_then(|v| v.as_str())
+        .expect("IDL missing program address");
+    let metadata = metadata_consts(idl);
+
+    // Events emitted through emit_cpi share the instruction namespace. Programs without events
+    // (including non-Anchor programs with short instruction tags) don't get the emit_cpi arm, so it
+    // can't collide with one of their instructions.
+    if !event_match_arms.is_empty() {
+        let emit_cpi_len = EMIT_CPI_INSTRUCTION_DISCRIMINATOR.len();
+        match_arms.push((EMIT_CPI_INSTRUCTION_DISCRIMINATOR.to_vec(), quote! {
+            return decode_event(&data[#emit_cpi_len..]).map(#decoded_instruction::EmitCpi);
+        }));
+    }
+
+    let name_dispatch = dispatch(name_arms);
+    let instruction_dispatch = dispatch(match_arms);
+    let account_dispatch = dispatch(account_match_arms);
+    let event_dispatch = dispatch(event_match_arms);
+
+    let program_docs = program_doc_lines(idl);
+    // The decoded enums hold items of every kind, so only the top-level options apply to them.
+    let decoded_attrs = options.attrs(&[]);
+
+    // Only decoders with left out items get an `Ignored` variant, so matches on the decoded enums
+    // of unfiltered decoders stay exhaustive.
+    if ignores_instructions {
+        enum_variants.push(quote! {
+            /// An instruction in the IDL that was left out of generation, by IDL name
+            Ignored(&'static str)
+        });
+        variant_name_arms.push(quote! { #decoded_instruction::Ignored(name) => name });
+        variant_account_index_arms.push(quote! { #decoded_instruction::Ignored(_) => None });
+        variant_map_accounts_arms.push(quote! {
+            #decoded_instruction::Ignored(_) => ::std::collections::HashMap::new()
+        });
+        variant_account_metas_arms.push(quote! { #decoded_instruction::Ignored(_) => &[] });
+        variant_verify_pdas_arms.push(quote! { #decoded_instruction::Ignored(_) => {} });
+        variant_summary_arms.push(quote! {
+            #decoded_instruction::Ignored(name) => write!(out, "{}(..)", name)
+        });
+    }
+    if ignores_accounts {
+        account_enum_variants.push(quote! {
+            /// An account in the IDL that was left out of generation, by IDL name
+            Ignored(&'static str)
+        });
+        account_summary_arms.push(quote! {
+            #decoded_account::Ignored(name) => write!(out, "{}(..)", name)
+        });
+    }
+    if ignores_events {
+        event_enum_variants.push(quote! {
+            /// An event in the IDL that was left out of generation, by IDL name
+            Ignored(&'static str)
+        });
+        event_summary_arms.push(quote! {
+            #decoded_event::Ignored(name) => write!(out, "{}(..)", name)
+        });
+    }
+
+    // Programs without PDA seeds in their IDL have nothing to verify.
+    let verify_pdas = if any_pda_checks {
+        let account_data = (!uses_account_data).then(|| quote! { let _ = &account_data; });
+        quote! {
+            #account_data
+            let mut violations = Vec::new();
+            match self {
+                #( #variant_verify_pdas_arms, )*
+                #decoded_instruction::EmitCpi(_) => {}
+            }
+            if violations.is_empty() {
+                Ok(())
+            } else {
+                Err(violations)
+            }
+        }
+    } else {
+        quote! {
+            let _ = (self, accounts, &account_data);
+            Ok(())
+        }
+    };
+
+    variant_summary_arms.push(variant_summary_arm(
+        quote! { #decoded_instruction::EmitCpi(value) },
+        "emit_cpi",
+        false,
+    ));
+    let instruction_summary = decoded_summary(&decoded_instruction, &variant_summary_arms);
+    let account_summary = decoded_summary(&decoded_account, &account_summary_arms);
+    let event_summary = decoded_summary(&decoded_event, &event_summary_arms);
+
+    let pubkey_field = if pubkey_field_arms.is_empty() {
+        quote! {
+            let _ = (self, name);
+            None
+        }
+    } else {
+        quote! {
+            match (self, name) {
+                #( #pubkey_field_arms, )*
+                _ => None,
+            }
+        }
+    };
+
+    let expanded = quote! {
+        /// Program address from the IDL
+        pub const IDL_ADDRESS: ::solana_sdk::pubkey::Pubkey = ::solana_sdk::pubkey!(#program_address);
+
+        #metadata
+
+        #( #struct_defs )*
+
+        #[derive(Debug)]
+        #decoded_attrs
+        pub enum #decoded_instruction {
+            #( #enum_variants, )*
+            EmitCpi(#decoded_event)
+        }
+
+        impl #decoded_instruction {
+            /// Returns the IDL name of the instruction
+            pub fn name(&self) -> &'static str {
+                match self {
+                    #( #variant_name_arms, )*
+                    #decoded_instruction::EmitCpi(_) => "emit_cpi",
+                }
+            }
+
+            /// Returns the position of an account in the instruction's account list by its IDL name
+            pub fn get_account_index(&self, name: &str) -> Option<usize> {
+                match self {
+                    #( #variant_account_index_arms, )*
+                    #decoded_instruction::EmitCpi(_) => None,
+                }
+            }
+
+            /// Maps account keys to the instruction's IDL account names
+            pub fn map_accounts<'a>(&self, accounts: &'a [::solana_sdk::pubkey::Pubkey]) -> ::std::collections::HashMap<&'static str, &'a ::solana_sdk::pubkey::Pubkey> {
+                match self {
+                    #( #variant_map_accounts_arms, )*
+                    #decoded_instruction::EmitCpi(_) => ::std::collections::HashMap::new(),
+                }
+            }
+
+            /// Returns the IDL metadata of the instruction's accounts, by account index
+            pub fn account_metas(&self) -> &'static [::anchor_decoder_runtime::IdlAccountMeta] {
+                match self {
+                    #( #variant_account_metas_arms, )*
+                    #decoded_instruction::EmitCpi(_) => &[],
+                }
+            }
+
+            /// Checks the instruction's accounts, with the signer and writable flags from the
+            /// message, against the IDL constraints that can be checked offline: fixed addresses,
+            /// signer and writable flags, missing accounts and, for related accounts whose data
+            /// `account_data` returns, `relations`. Returns every violation found, naming accounts
+            /// in composite groups `<group>.<account>`.
+            pub fn validate<'d>(
+                &self,
+                accounts: &[::solana_sdk::instruction::AccountMeta],
+                account_data: impl Fn(&::solana_sdk::pubkey::Pubkey) -> Option<&'d [u8]>,
+            ) -> Result<(), Vec<::anchor_decoder_runtime::ConstraintViolation>> {
+                let violations = ::anchor_decoder_runtime::check_account_constraints(
+                    &ID,
+                    self.account_metas(),
+                    accounts,
+                    |key, field| decode_account(account_data(key)?)?.pubkey_field(field),
+                );
+                if violations.is_empty() {
+                    Ok(())
+                } else {
+                    Err(violations)
+                }
+            }
+
+            /// Re-derives the instruction's PDA accounts from the `pda` seeds in the IDL, using
+            /// the instruction's args and account keys and, for seeds read from other accounts,
+            /// the data `account_data` returns, and checks them against the keys passed. PDAs
+            /// whose seeds aren't available are skipped. Returns every mismatch found.
+            pub fn verify_pdas<'d>(
+                &self,
+                accounts: &[::solana_sdk::pubkey::Pubkey],
+                account_data: impl Fn(&::solana_sdk::pubkey::Pubkey) -> Option<&'d [u8]>,
+            ) -> Result<(), Vec<::anchor_decoder_runtime::ConstraintViolation>> {
+                #verify_pdas
+            }
+        }
+
+        #instruction_summary
+
+        /// An instruction targeting this program found in a transaction, either at the top level
+        /// or invoked through CPI
+        #[derive(Debug)]
+        pub struct #decoded_transaction_instruction {
+            /// Index of the top-level instruction, or of the top-level instruction that invoked it
+            pub instruction_index: usize,
+            /// Index within the inner instructions of `instruction_index`, or `None` for top-level
+            /// instructions
+            pub inner_index: Option<usize>,
+            /// Invocation stack height, starting at 1 for top-level instructions
+            pub stack_height: u8,
+            /// Account keys passed to the instruction, in order
+            pub accounts: Vec<::solana_sdk::pubkey::Pubkey>,
+            pub instruction: #decoded_instruction,
+        }
+
+        impl #decoded_transaction_instruction {
+            /// Looks up an account key by its IDL name
+            pub fn account(&self, name: &str) -> Option<&::solana_sdk::pubkey::Pubkey> {
+                self.accounts.get(self.instruction.get_account_index(name)?)
+            }
+
+            /// Maps the account keys to the instruction's IDL account names
+            pub fn named_accounts(&self) -> ::std::collections::HashMap<&'static str, &::solana_sdk::pubkey::Pubkey> {
+                self.instruction.map_accounts(&self.accounts)
+            }
+
+            /// Pairs each account key with its IDL metadata. Keys past the accounts in the IDL
+            /// (remaining accounts) have no metadata.
+            pub fn accounts_with_meta(&self) -> impl Iterator<Item = (&::solana_sdk::pubkey::Pubkey, Option<&'static ::anchor_decoder_runtime::IdlAccountMeta>)> + '_ {
+                let metas = self.instruction.account_metas();
+                self.accounts.iter().enumerate().map(move |(index, key)| (key, metas.get(index)))
+            }
+        }
+
+        /// Summarized as the instruction followed by its accounts as `name=address` pairs, the
+        /// signers and writable accounts at `Verbosity::Normal` and all of them at
+        /// `Verbosity::Full`
+        impl ::anchor_decoder_runtime::Summarize for #decoded_transaction_instruction {
+            fn summarize(&self, out: &mut dyn ::std::fmt::Write, verbosity: ::anchor_decoder_runtime::Verbosity) -> ::std::fmt::Result {
+                ::anchor_decoder_runtime::Summarize::summarize(&self.instruction, out, verbosity)?;
+                ::anchor_decoder_runtime::write_instruction_accounts(
+                    out,
+                    &ID,
+                    self.instruction.account_metas(),
+                    &self.accounts,
+                    verbosity,
+                )
+            }
+        }
+
+        /// Writes the summary at the default verbosity
+        impl ::std::fmt::Display for #decoded_transaction_instruction {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::anchor_decoder_runtime::Summarize::summarize(self, f, ::anchor_decoder_runtime::Verbosity::default())
+            }
+        }
+
+        /// Decodes every instruction in the transaction that targets this program, including
+        /// instructions invoked through CPI when `inner_instructions` is provided. Inner
+        /// instructions are given as the transaction metadata records them: pairs of the index of
+        /// the top-level instruction that invoked them and the instructions, in invocation order.
+        /// Top-level instructions without inner instructions have no entry.
+        ///
+        /// Account indices are resolved against the full account key list (see
+        /// `resolve_account_keys`). For v0 messages that use address lookup tables, pass the
+        /// `loaded_addresses` from the transaction metadata or from `load_lookup_table_addresses`;
+        /// without them, instructions referencing looked-up accounts are skipped.
+        pub fn decode_transaction(
+            transaction: &::solana_sdk::transaction::VersionedTransaction,
+            loaded_addresses: Option<&::solana_sdk::message::v0::LoadedAddresses>,
+            inner_instructions: Option<&[(u8, ::solana_sdk::inner_instruction::InnerInstructions)]>,
+        ) -> Vec<#decoded_transaction_instruction> {
+            let account_keys = resolve_account_keys(&transaction.message, loaded_addresses);
+            let account_keys = account_keys.as_slice();
+            let mut decoded = Vec::new();
+            for (instruction_index, instruction) in transaction.message.instructions().iter().enumerate() {
+                if let Some((accounts, ix)) = __anchor_decoder::decode_compiled_instruction(instruction, account_keys) {
+                    decoded.push(#decoded_transaction_instruction {
+                        instruction_index,
+                        inner_index: None,
+                        stack_height: 1,
+                        accounts,
+                        instruction: ix,
+                    });
+                }
+
+                let inner = inner_instructions
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|(index, _)| usize::from(*index) == instruction_index)
+                    .flat_map(|(_, instructions)| instructions);
+                for (inner_index, inner_instruction) in inner.enumerate() {
+                    if let Some((accounts, ix)) = __anchor_decoder::decode_compiled_instruction(&inner_instruction.instruction, account_keys) {
+                        decoded.push(#decoded_transaction_instruction {
+                            instruction_index,
+                            inner_index: Some(inner_index),
+                            stack_height: inner_instruction.stack_height,
+                            accounts,
+                            instruction: ix,
+                        });
+                    }
+                }
+            }
+            decoded
+        }
+
+        /// Returns the message's full account key list, in the order compiled instructions index
+        /// into it: the static keys, then the writable and then the readonly addresses loaded from
+        /// address lookup tables
+        pub fn resolve_account_keys(
+            message: &::solana_sdk::message::VersionedMessage,
+            loaded_addresses: Option<&::solana_sdk::message::v0::LoadedAddresses>,
+        ) -> Vec<::solana_sdk::pubkey::Pubkey> {
+            let mut account_keys = message.static_account_keys().to_vec();
+            if let Some(loaded_addresses) = loaded_addresses {
+                account_keys.extend_from_slice(&loaded_addresses.writable);
+                account_keys.extend_from_slice(&loaded_addresses.readonly);
+            }
+            account_keys
+        }
+
+        /// Resolves the addresses a v0 message loads from address lookup tables, given the raw
+        /// account data of every lookup table it references. Legacy messages load no addresses.
+        ///
+        /// The tables are read as-is: addresses appended after the transaction executed, or a
+        /// table closed since, will resolve differently than they did on chain.
+        pub fn load_lookup_table_addresses(
+            message: &::solana_sdk::message::VersionedMessage,
+            lookup_tables: &[(::solana_sdk::pubkey::Pubkey, &[u8])],
+        ) -> Result<::solana_sdk::message::v0::LoadedAddresses, #lookup_table_error> {
+            let mut loaded_addresses = ::solana_sdk::message::v0::LoadedAddresses::default();
+            for lookup in message.address_table_lookups().unwrap_or_default() {
+                let table_key = lookup.account_key;
+                let (_, data) = lookup_tables
+                    .iter()
+                    .find(|(key, _)| *key == table_key)
+                    .ok_or(#lookup_table_error::MissingTable(table_key))?;
+                let table = ::solana_sdk::address_lookup_table::state::AddressLookupTable::deserialize(data)
+                    .map_err(|_| #lookup_table_error::InvalidTableData(table_key))?;
+                let lookup_address = |index: &u8| {
+                    table
+                        .addresses
+                        .get(*index as usize)
+                        .copied()
+                        .ok_or(#lookup_table_error::InvalidIndex { table: table_key, index: *index })
+                };
+                for index in &lookup.writable_indexes {
+                    loaded_addresses.writable.push(lookup_address(index)?);
+                }
+                for index in &lookup.readonly_indexes {
+                    loaded_addresses.readonly.push(lookup_address(index)?);
+                }
+            }
+            Ok(loaded_addresses)
+        }
+
+        #[derive(Debug)]
+        pub enum #lookup_table_error {
+            /// No data was provided for a lookup table the message references
+            MissingTable(::solana_sdk::pubkey::Pubkey),
+            /// The lookup table account data could not be deserialized
+            InvalidTableData(::solana_sdk::pubkey::Pubkey),
+            /// The message references an index past the end of the lookup table
+            InvalidIndex { table: ::solana_sdk::pubkey::Pubkey, index: u8 },
+        }
+
+        impl ::std::fmt::Display for #lookup_table_error {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match self {
+                    #lookup_table_error::MissingTable(table) => write!(f, "missing address lookup table {}", table),
+                    #lookup_table_error::InvalidTableData(table) => write!(f, "invalid address lookup table data for {}", table),
+                    #lookup_table_error::InvalidIndex { table, index } => {
+                        write!(f, "index {} out of bounds for address lookup table {}", index, table)
+                    }
+                }
+            }
+        }
+
+        impl ::std::error::Error for #lookup_table_error {}
+
+        /// Instruction names and discriminators, in IDL order
+        pub const INSTRUCTION_DISCRIMINATORS: &[(&str, &[u8])] = &[
+            #( #discriminator_entries, )*
+        ];
+
+        /// Returns the IDL name of the instruction without decoding its arguments
+        pub fn instruction_name(data: &[u8]) -> Option<&'static str> {
+            #name_dispatch
+            None
+        }
+
+        pub fn decode_instruction(data: &[u8]) -> Option<#decoded_instruction> {
+            #instruction_dispatch
+            None
+        }
+
+        #[derive(Debug)]
+        #decoded_attrs
+        pub enum #decoded_account {
+            #( #account_enum_variants, )*
+        }
+
+        impl #decoded_account {
+            /// Returns the value of one of the account's `Pubkey` fields, by IDL name
+            pub fn pubkey_field(&self, name: &str) -> Option<::solana_sdk::pubkey::Pubkey> {
+                #pubkey_field
+            }
+        }
+
+        #account_summary
+
+        pub fn decode_account(data: &[u8]) -> Option<#decoded_account> {
+            __anchor_decoder::try_decode_account(data).ok()
+        }
+
+        /// Decodes an account after checking that it is owned by this program. Matching on the
+        /// discriminator alone would accept an account created by any other program that happens
+        /// to start with the same 8 bytes.
+        pub fn decode_account_info(owner: &::solana_sdk::pubkey::Pubkey, data: &[u8]) -> Result<#decoded_account, #account_decode_error> {
+            __anchor_decoder::check_account_owner(owner)?;
+            __anchor_decoder::try_decode_account(data)
+        }
+
+        impl TryFrom<&::solana_sdk::account::Account> for #decoded_account {
+            type Error = #account_decode_error;
+
+            fn try_from(account: &::solana_sdk::account::Account) -> Result<Self, Self::Error> {
+                decode_account_info(&account.owner, &account.data)
+            }
+        }
+
+        #[derive(Debug)]
+        pub enum #account_decode_error {
+            /// The account is owned by a program other than `ID`
+            InvalidOwner { expected: ::solana_sdk::pubkey::Pubkey, actual: ::solana_sdk::pubkey::Pubkey },
+            /// The account data is too short or does not start with a known discriminator
+            UnknownDiscriminator,
+            /// The discriminator matched but the remaining data failed to deserialize
+            InvalidData(::std::io::Error),
+        }
+
+        impl ::std::fmt::Display for #account_decode_error {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match self {
+                    #account_decode_error::InvalidOwner { expected, actual } => {
+                        write!(f, "account owner mismatch: expected {}, found {}", expected, actual)
+                    }
+                    #account_decode_error::UnknownDiscriminator => write!(f, "unknown account discriminator"),
+                    #account_decode_error::InvalidData(e) => write!(f, "failed to deserialize account data: {}", e),
+                }
+            }
+        }
+
+        impl ::std::error::Error for #account_decode_error {}
+
+        /// A `getProgramAccounts` filter, mirroring the RPC's `memcmp` and `dataSize` filters
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum #account_filter {
+            /// Matches accounts whose data contains `bytes` starting at `offset`
+            Memcmp { offset: usize, bytes: Vec<u8> },
+            /// Matches accounts whose data is exactly this many bytes long
+            DataSize(u64),
+        }
+
+        /// Account names and discriminators, in IDL order
+        pub const ACCOUNT_DISCRIMINATORS: &[(&str, &[u8])] = &[
+            #( #account_discriminator_entries, )*
+        ];
+
+        #[derive(Debug)]
+        #decoded_attrs
+        pub enum #decoded_event {
+            #( #event_enum_variants, )*
+        }
+
+        #event_summary
+
+        /// Event names and discriminators, in IDL order
+        pub const EVENT_DISCRIMINATORS: &[(&str, &[u8])] = &[
+            #( #event_discriminator_entries, )*
+        ];
+
+        pub fn decode_event(data: &[u8]) -> Option<#decoded_event> {
+            #event_dispatch
+            None
+        }
+
+        /// Support functions for the generated items, kept in their own module so they don't
+        /// clash with items in the surrounding module
+        mod __anchor_decoder {
+            use super::*;
+
+            /// Decodes a compiled instruction if it targets this program, resolving its account
+            /// indices against `account_keys`
+            pub(super) fn decode_compiled_instruction(
+                instruction: &::solana_sdk::instruction::CompiledInstruction,
+                account_keys: &[::solana_sdk::pubkey::Pubkey],
+            ) -> Option<(Vec<::solana_sdk::pubkey::Pubkey>, #decoded_instruction)> {
+                if *account_keys.get(instruction.program_id_index as usize)? != ID {
+                    return None;
+                }
+                let accounts = instruction
+                    .accounts
+                    .iter()
+                    .map(|index| account_keys.get(*index as usize).copied())
+                    .collect::<Option<Vec<_>>>()?;
+                Some((accounts, decode_instruction(&instruction.data)?))
+            }
+
+            /// Reads a discriminator of up to 8 bytes from the start of `data` as a little-endian
+            /// integer, zero-extended to 64 bits
+            #[allow(dead_code)]
+            #[inline(always)]
+            pub(super) fn read_discriminator(data: &[u8], len: usize) -> Option<u64> {
+                let disc = data.get(..len)?;
+                let mut bytes = [0u8; 8];
+                bytes[..len].copy_from_slice(disc);
+                Some(u64::from_le_bytes(bytes))
+            }
+
+            pub(super) fn view_field<T: ::borsh::BorshDeserialize>(data: &[u8], offset: usize) -> ::std::io::Result<T> {
+                let mut buf = data.get(offset..).ok_or_else(|| {
+                    ::std::io::Error::new(::std::io::ErrorKind::UnexpectedEof, "field offset is past the end of the data")
+                })?;
+                <T as ::borsh::BorshDeserialize>::deserialize(&mut buf)
+            }
+
+            /// Deserializes the field at `offset` only to find where the next field starts
+            #[allow(dead_code)]
+            pub(super) fn skip_field<T: ::borsh::BorshDeserialize>(data: &[u8], offset: usize) -> ::std::io::Result<usize> {
+                let mut buf = data.get(offset..).ok_or_else(|| {
+                    ::std::io::Error::new(::std::io::ErrorKind::UnexpectedEof, "field offset is past the end of the data")
+                })?;
+                <T as ::borsh::BorshDeserialize>::deserialize(&mut buf)?;
+                Ok(data.len() - buf.len())
+            }
+
+            pub(super) fn check_account_owner(owner: &::solana_sdk::pubkey::Pubkey) -> Result<(), #account_decode_error> {
+                if *owner != ID {
+                    return Err(#account_decode_error::InvalidOwner { expected: ID, actual: *owner });
+                }
+                Ok(())
+            }
+
+            pub(super) fn try_decode_account(data: &[u8]) -> Result<#decoded_account, #account_decode_error> {
+                #account_dispatch
+                Err(#account_decode_error::UnknownDiscriminator)
+            }
+        }
+
+        #( #[doc = #program_docs] )*
+        ///
+        /// Implements `anchor_decoder_runtime::ProgramDecoder`, for use with a `DecoderRegistry`.
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct #decoder;
+
+        impl ::anchor_decoder_runtime::ProgramDecoder for #decoder {
+            type Instruction = #decoded_instruction;
+            type Account = #decoded_account;
+            type Event = #decoded_event;
+
+            fn program_id(&self) -> ::solana_sdk::pubkey::Pubkey {
+                ID
+            }
+
+            fn program_name(&self) -> &'static str {
+                PROGRAM_NAME
+            }
+
+            fn program_version(&self) -> &'static str {
+                PROGRAM_VERSION
+            }
+
+            fn idl_hash(&self) -> &'static str {
+                IDL_HASH
+            }
+
+            fn decode_instruction(&self, data: &[u8]) -> Option<#decoded_instruction> {
+                decode_instruction(data)
+            }
+
+            fn decode_account(&self, data: &[u8]) -> Option<#decoded_account> {
+                decode_account(data)
+            }
+
+            fn decode_event(&self, data: &[u8]) -> Option<#decoded_event> {
+                decode_event(data)
+            }
+        }
+    };
+
+    expanded
+}
+
+/// Generates the decoder for the IDL at `idl_path` and writes it, formatted, to `out_path`.
+/// Meant to be called from a build script, so the generated code can be checked in instead of
+/// expanded by the macro on every build:
+///
+/// ```no_run
+/// // in build.rs `main`
+/// println!("cargo:rerun-if-changed=idl.json");
+/// anchor_decoder_codegen::generate_to_file("idl.json", "src/generated.rs").unwrap();
+/// ```
+///
+/// The file starts with module docs describing the program and allows the lints that IDL names
+/// trip, like `non_snake_case`, so declare it as a module (`mod generated;`) rather than
+/// `include!`ing it. Relative paths are resolved against the
+/// working directory, which for build scripts is the package root.
+pub fn generate_to_file(idl_path: impl AsRef<Path>, out_path: impl AsRef<Path>) -> io::Result<()> {
+    generate_to_file_with(idl_path, out_path, &Options::default())
+}
+
+/// Like [`generate_to_file`], with extra derives and attributes on the generated types (see
+/// [`Options`])
+pub fn generate_to_file_with(
+    idl_path: impl AsRef<Path>,
+    out_path: impl AsRef<Path>,
+    options: &Options,
+) -> io::Result<()> {
+    let idl_path = idl_path.as_ref();
+    let idl_json = std::fs::read_to_string(idl_path)?;
+    let idl: Value = serde_json::from_str(&idl_json)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let header = format!(
+        "// @generated by anchor-decoder-codegen from {}. Do not edit by hand.\n\n",
+        idl_path.display()
+    );
+    let module_docs = program_doc_lines(&idl);
+    let tokens = generate_with(&idl, options);
+    std::fs::write(
+        out_path,
+        header
+            + &format_tokens(quote! {
+                #( #![doc = #module_docs] )*
+                // Names follow the IDL, which may not use Rust's casing. The macro's expansion
+                // isn't linted, so neither is the same code written to a file.
+                #![allow(non_snake_case, non_camel_case_types, clippy::all)]
+
+                pub const ID: ::solana_sdk::pubkey::Pubkey = IDL_ADDRESS;
+
+                #tokens
+            }),
+    )
+}
+
+/// Formats generated tokens as Rust source
+pub fn format_tokens(tokens: proc_macro2::TokenStream) -> String {
+    let file: syn::File = syn::parse2(tokens).expect("generated code is valid Rust");
+    prettyplease::unparse(&file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snake_case() {
+        assert_eq!(to_snake_case("fee_recipient"), "fee_recipient");
+        assert_eq!(to_snake_case("feeRecipient"), "fee_recipient");
+        assert_eq!(to_snake_case("initialVirtualSolReserves"), "initial_virtual_sol_reserves");
+        assert_eq!(to_snake_case("NFTMint"), "nft_mint");
+        assert_eq!(to_snake_case("tokenV2Amount"), "token_v2_amount");
+        assert_eq!(to_snake_case("reward_growths_outside"), "reward_growths_outside");
+    }
+}
@@ -0,0 +1,80 @@
+use quote::quote;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Generates constants for the IDL `metadata` and a hash identifying the exact IDL the decoder
+/// was generated from.
+pub fn metadata_consts(idl: &Value) -> proc_macro2::TokenStream {
+    let metadata = idl.get("metadata");
+    let field = |key: &str| metadata.and_then(|m| m.get(key)).and_then(|v| v.as_str());
+    let required =
+        |key: &str| field(key).unwrap_or_else(|| panic!("IDL missing `{}` in metadata", key));
+    let optional = |key: &str| match field(key) {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    };
+
+    let name = required("name");
+    let version = required("version");
+    let spec = required("spec");
+    let description = optional("description");
+    let repository = optional("repository");
+    let hash = idl_hash(idl);
+
+    quote! {
+        /// Program name from the IDL metadata
+        pub const PROGRAM_NAME: &str = #name;
+        /// Program version from the IDL metadata
+        pub const PROGRAM_VERSION: &str = #version;
+        /// IDL specification version from the IDL metadata
+        pub const IDL_SPEC: &str = #spec;
+        /// Program description from the IDL metadata, if any
+        pub const PROGRAM_DESCRIPTION: Option<&str> = #description;
+        /// Program repository from the IDL metadata, if any
+        pub const PROGRAM_REPOSITORY: Option<&str> = #repository;
+        /// Hex-encoded SHA-256 of the canonicalized IDL JSON (object keys sorted, no whitespace).
+        /// Reformatting the IDL file doesn't change it; any change to its content does.
+        pub const IDL_HASH: &str = #hash;
+    }
+}
+
+/// Hashes the IDL's canonical JSON encoding, so the hash doesn't depend on formatting, key
+/// order or whether `serde_json` was built with `preserve_order`.
+pub fn idl_hash(idl: &Value) -> String {
+    let mut canonical = String::new();
+    write_canonical(idl, &mut canonical);
+    Sha256::digest(canonical.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<_> = object.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::idl::{ident, list, name};
+use crate::to_camel_case;
+
+/// Names of the support items generated for every IDL
+const SUPPORT_NAMES: &[&str] = &[
+    "AccountDecodeError",
+    "AccountFilter",
+    "DecodedAccount",
+    "DecodedEvent",
+    "DecodedInstruction",
+    "DecodedTransactionInstruction",
+    "Decoder",
+    "LookupTableError",
+];
+
+/// Names of the items generated for an IDL's instructions and accounts and of the support items
+/// generated for every IDL (`Decoder`, `DecodedInstruction`, ...), which share a namespace with
+/// the IDL's types.
+///
+/// IDL types keep their names, since they describe the program's data. A support item is named
+/// `Anchor<Item>` if an IDL type takes its name, e.g. `AnchorDecoder`. An instruction's structs
+/// are named `<Instruction>`, `<Instruction>Accounts` and `<Instruction>Keys`, or
+/// `<Instruction>Instruction`, `<Instruction>InstructionAccounts` and
+/// `<Instruction>InstructionKeys` if any of those names is taken. An account's view is named
+/// `<Account>View`, or `<Account>AccountView` if that's taken. Names are claimed in that order:
+/// types, then support items, then instructions, then views.
+pub struct GeneratedNames {
+    support: HashMap<&'static str, String>,
+    instructions: HashMap<String, [String; 3]>,
+    views: HashMap<String, String>,
+    /// Names that are still taken after falling back, as the item, the name and what already
+    /// claimed it
+    pub collisions: Vec<(String, String, String)>,
+}
+
+impl GeneratedNames {
+    pub fn new(idl: &Value) -> Self {
+        let mut claimed: HashMap<String, String> = HashMap::new();
+        let mut collisions = Vec::new();
+
+        for type_name in list(idl, "types").iter().filter_map(name) {
+            let origin = format!("type `{}`", type_name);
+            if let Some(existing) = claimed.get(type_name) {
+                // Types with the same name are reported as duplicates.
+                if *existing != origin {
+                    collisions.push((origin, type_name.to_string(), existing.clone()));
+                }
+                continue;
+            }
+            claimed.insert(type_name.to_string(), origin);
+        }
+
+        let mut support = HashMap::new();
+        for item in SUPPORT_NAMES {
+            let name = match claimed.get(*item) {
+                None => item.to_string(),
+                Some(type_origin) => {
+                    let fallback = format!("Anchor{}", item);
+                    if let Some(existing) = claimed.get(&fallback) {
+                        collisions.push((type_origin.clone(), fallback.clone(), existing.clone()));
+                    }
+                    fallback
+                }
+            };
+            claimed.insert(name.clone(), "a generated item".to_string());
+            support.insert(*item, name);
+        }
+
+        let mut instructions = HashMap::new();
+        for instruction in list(idl, "instructions").iter().filter_map(name) {
+            let origin = format!("instruction `{}`", instruction);
+            let camel = to_camel_case(instruction);
+            let candidates = [camel.clone(), format!("{}Instruction", camel)].map(|base| {
+                [
+                    base.clone(),
+                    format!("{}Accounts", base),
+                    format!("{}Keys", base),
+                ]
+            });
+            let free = candidates
+                .iter()
+                .find(|structs| structs.iter().all(|name| !claimed.contains_key(name)))
+                .cloned();
+            let structs = match free {
+                Some(structs) => structs,
+                None => {
+                    let structs = candidates[1].clone();
+                    for taken in &structs {
+                        if let Some(existing) = claimed.get(taken) {
+                            collisions.push((origin.clone(), taken.clone(), existing.clone()));
+                        }
+                    }
+                    structs
+                }
+            };
+            for name in &structs {
+                claimed.insert(name.clone(), origin.clone());
+            }
+            instructions.insert(instruction.to_string(), structs);
+        }
+
+        let mut views = HashMap::new();
+        for account in list(idl, "accounts").iter().filter_map(name) {
+            let origin = format!("account `{}`", account);
+            let candidates = [
+                format!("{}View", account),
+                format!("{}AccountView", account),
+            ];
+            let view = match candidates.iter().find(|view| !claimed.contains_key(*view)) {
+                Some(view) => view.clone(),
+                None => {
+                    let view = candidates[1].clone();
+                    collisions.push((origin.clone(), view.clone(), claimed[&view].clone()));
+                    view
+                }
+            };
+            claimed.insert(view.clone(), origin);
+            views.insert(account.to_string(), view);
+        }
+
+        GeneratedNames {
+            support,
+            instructions,
+            views,
+            collisions,
+        }
+    }
+
+    /// Returns the ident of a support item, given its usual name, e.g. `Decoder`
+    pub fn support(&self, item: &str) -> syn::Ident {
+        ident(&self.support[item])
+    }
+
+    /// Returns the idents of an instruction's argument struct, accounts struct and keys struct
+    pub fn instruction(&self, instruction: &str) -> (syn::Ident, syn::Ident, syn::Ident) {
+        let [args, accounts, keys] = &self.instructions[instruction];
+        (ident(args), ident(accounts), ident(keys))
+    }
+
+    /// Returns the ident of an account's view type
+    pub fn view(&self, account: &str) -> syn::Ident {
+        ident(&self.views[account])
+    }
+}
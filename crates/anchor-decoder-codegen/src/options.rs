@@ -0,0 +1,536 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde_json::Value;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Expr, Lit, Meta, Path, Token};
+
+use crate::idl::{list, name};
+
+/// Options controlling the generated code, written the same way as the `anchor_idl` macro's
+/// arguments after the IDL path:
+///
+/// ```
+/// let options: anchor_decoder_codegen::Options =
+///     "derive(Clone, PartialEq), accounts(derive(Eq, Hash)), events(attr(non_exhaustive))"
+///         .parse()
+///         .unwrap();
+/// ```
+///
+/// Top-level `derive(...)` and `attr(...)` apply to every generated data type: the IDL's types,
+/// instruction argument structs, and the `DecodedInstruction`, `DecodedAccount` and
+/// `DecodedEvent` enums. `accounts(...)`, `args(...)`, `events(...)` and `types(...)` apply only
+/// to account structs, instruction argument structs, event structs and the remaining IDL types
+/// respectively. Types nested in an account, argument or event also get that kind's options, so
+/// derives like `PartialEq` compile without listing every nested type.
+///
+/// `Default` is implemented rather than derived: enums default to their first variant, and
+/// arrays of any length are filled with their element's default.
+///
+/// Instructions, accounts and events can be left out by IDL name with
+/// `instructions(include = ["swap*"], exclude = [...])` (likewise inside `accounts(...)` and
+/// `events(...)`), or all at once with `events = false`. Left out items still have their
+/// discriminators dispatched, decoding to an `Ignored` variant with the item's name instead of
+/// being treated as unknown. Types only used by left out items aren't generated either.
+#[derive(Clone, Default)]
+pub struct Options {
+    pub all: ItemOptions,
+    pub accounts: ItemOptions,
+    pub args: ItemOptions,
+    pub events: ItemOptions,
+    pub types: ItemOptions,
+    pub instruction_filter: Filter,
+    pub account_filter: Filter,
+    pub event_filter: Filter,
+}
+
+/// Derives and attributes added to a kind of generated item
+#[derive(Clone, Default)]
+pub struct ItemOptions {
+    /// Derived alongside the `Debug` and Borsh derives every generated type has
+    pub derives: Vec<Path>,
+    /// Contents of each attribute, e.g. `serde(rename_all = "camelCase")`
+    pub attrs: Vec<TokenStream>,
+}
+
+/// Selects items by IDL name. Patterns may use `*` to match any run of characters and `?` to
+/// match a single character.
+#[derive(Clone, Default)]
+pub struct Filter {
+    /// Patterns of the items to generate, or `None` to generate everything not excluded
+    pub include: Option<Vec<String>>,
+    /// Patterns of the items to leave out, even if included
+    pub exclude: Vec<String>,
+}
+
+impl Filter {
+    /// Leaves out every item
+    pub fn none() -> Self {
+        Filter {
+            include: Some(Vec::new()),
+            exclude: Vec::new(),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let included = match &self.include {
+            Some(patterns) => patterns.iter().any(|pattern| glob_match(pattern, name)),
+            None => true,
+        };
+        included && !self.exclude.iter().any(|pattern| glob_match(pattern, name))
+    }
+
+    fn parse_meta(&mut self, meta: &syn::MetaNameValue) -> syn::Result<()> {
+        let patterns = patterns(&meta.value)?;
+        if meta.path.is_ident("include") {
+            self.include.get_or_insert_with(Vec::new).extend(patterns);
+        } else if meta.path.is_ident("exclude") {
+            self.exclude.extend(patterns);
+        } else {
+            return Err(syn::Error::new_spanned(
+                &meta.path,
+                "expected `include = [...]` or `exclude = [...]`",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Reads a string literal or an array of string literals
+fn patterns(value: &Expr) -> syn::Result<Vec<String>> {
+    let pattern = |expr: &Expr| match expr {
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Str(s), ..
+        }) => Ok(s.value()),
+        _ => Err(syn::Error::new_spanned(expr, "expected a string pattern")),
+    };
+    match value {
+        Expr::Array(array) => array.elems.iter().map(pattern).collect(),
+        _ => Ok(vec![pattern(value)?]),
+    }
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), name.chars().collect());
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` in the pattern, and of the name when it was reached
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` match one more character and retry.
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Kinds of generated items that options can target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Kind {
+    Accounts,
+    Args,
+    Events,
+    Types,
+}
+
+impl Parse for Options {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Options::default();
+        for meta in Punctuated::<Meta, Token![,]>::parse_terminated(input)? {
+            let (mut kind, mut filter) = match meta
+                .path()
+                .get_ident()
+                .map(|ident| ident.to_string())
+                .as_deref()
+            {
+                Some("instructions") => (None, Some(&mut options.instruction_filter)),
+                Some("accounts") => (
+                    Some(&mut options.accounts),
+                    Some(&mut options.account_filter),
+                ),
+                Some("args") => (Some(&mut options.args), None),
+                Some("events") => (Some(&mut options.events), Some(&mut options.event_filter)),
+                Some("types") => (Some(&mut options.types), None),
+                _ => {
+                    options.all.parse_meta(&meta)?;
+                    continue;
+                }
+            };
+
+            // `events = false` leaves out every item of that kind.
+            if let Meta::NameValue(name_value) = &meta {
+                let Some(filter) = filter else {
+                    return Err(syn::Error::new_spanned(
+                        &meta,
+                        "only `instructions`, `accounts` and `events` can be turned off",
+                    ));
+                };
+                match &name_value.value {
+                    Expr::Lit(syn::ExprLit {
+                        lit: Lit::Bool(enabled),
+                        ..
+                    }) => {
+                        if !enabled.value {
+                            *filter = Filter::none();
+                        }
+                    }
+                    value => {
+                        return Err(syn::Error::new_spanned(value, "expected `true` or `false`"))
+                    }
+                }
+                continue;
+            }
+
+            let nested = meta
+                .require_list()?
+                .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+            for nested in &nested {
+                match (nested, filter.as_deref_mut(), kind.as_deref_mut()) {
+                    (Meta::NameValue(name_value), Some(filter), _) => filter.parse_meta(name_value)?,
+                    (Meta::List(_), _, Some(kind)) => kind.parse_meta(nested)?,
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            nested,
+                            "expected `derive(...)` or `attr(...)`, or `include`/`exclude` for instructions, accounts and events",
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+impl FromStr for Options {
+    type Err = syn::Error;
+
+    fn from_str(s: &str) -> syn::Result<Self> {
+        syn::parse_str(s)
+    }
+}
+
+impl ItemOptions {
+    fn parse_meta(&mut self, meta: &Meta) -> syn::Result<()> {
+        let list = meta.require_list()?;
+        if list.path.is_ident("derive") {
+            let derives = list.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)?;
+            self.derives.extend(derives);
+        } else if list.path.is_ident("attr") {
+            self.attrs.push(list.tokens.clone());
+        } else {
+            return Err(syn::Error::new_spanned(
+                &list.path,
+                "expected `derive(...)`, `attr(...)`, or one of `accounts`, `args`, `events` or `types`",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Options {
+    fn kind(&self, kind: Kind) -> &ItemOptions {
+        match kind {
+            Kind::Accounts => &self.accounts,
+            Kind::Args => &self.args,
+            Kind::Events => &self.events,
+            Kind::Types => &self.types,
+        }
+    }
+
+    /// Returns the extra `#[derive]` and other attributes for an item of the given kinds, on top
+    /// of the top-level options. `Default` is left out; see [`Options::derives_default`].
+    pub(crate) fn attrs(&self, kinds: &[Kind]) -> TokenStream {
+        let mut seen = HashSet::new();
+        let mut derives = Vec::new();
+        let mut attrs = Vec::new();
+        let sources = std::iter::once(&self.all).chain(kinds.iter().map(|kind| self.kind(*kind)));
+        for options in sources {
+            for derive in &options.derives {
+                let name = derive.segments.last().map(|s| s.ident.to_string());
+                // Every generated type already derives these.
+                if matches!(
+                    name.as_deref(),
+                    Some("Debug" | "BorshSerialize" | "BorshDeserialize")
+                ) || name.as_deref() == Some("Default")
+                {
+                    continue;
+                }
+                if seen.insert(quote!(#derive).to_string()) {
+                    derives.push(derive);
+                }
+            }
+            attrs.extend(&options.attrs);
+        }
+        let derives = (!derives.is_empty()).then(|| quote! { #[derive( #( #derives ),* )] });
+        quote! {
+            #derives
+            #( #[#attrs] )*
+        }
+    }
+
+    /// Whether `Default` is derived for an item of the given kinds. The generated types implement
+    /// it by hand, since enums need a default variant and long arrays don't implement it.
+    pub(crate) fn derives_default(&self, kinds: &[Kind]) -> bool {
+        std::iter::once(&self.all)
+            .chain(kinds.iter().map(|kind| self.kind(*kind)))
+            .flat_map(|options| &options.derives)
+            .any(|derive| derive.segments.last().is_some_and(|s| s.ident == "Default"))
+    }
+}
+
+/// The parts of an IDL that decide which types get which options
+struct IdlItems {
+    type_defs: HashMap<String, Value>,
+    instructions: Vec<Value>,
+    accounts: Vec<String>,
+    events: Vec<String>,
+}
+
+impl IdlItems {
+    fn new(idl: &Value) -> Self {
+        IdlItems {
+            type_defs: list(idl, "types")
+                .iter()
+                .filter_map(|t| Some((name(t)?.to_string(), t.clone())))
+                .collect(),
+            instructions: list(idl, "instructions").to_vec(),
+            accounts: list(idl, "accounts")
+                .iter()
+                .filter_map(name)
+                .map(str::to_string)
+                .collect(),
+            events: list(idl, "events")
+                .iter()
+                .filter_map(name)
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// Types reachable from the arguments of the instructions
+    fn reached_from_instructions<'a>(
+        &self,
+        instructions: impl Iterator<Item = &'a Value>,
+    ) -> HashSet<String> {
+        let mut reached = HashSet::new();
+        for instruction in instructions {
+            if let Some(args) = instruction.get("args") {
+                collect_defined(args, &self.type_defs, &mut reached);
+            }
+        }
+        reached
+    }
+
+    /// Types reachable from the named account or event types, including the types themselves
+    fn reached_from_types<'a>(&self, names: impl Iterator<Item = &'a String>) -> HashSet<String> {
+        let mut reached = HashSet::new();
+        for name in names {
+            if let Some(type_def) = self.type_defs.get(name) {
+                reached.insert(name.clone());
+                collect_defined(type_def, &self.type_defs, &mut reached);
+            }
+        }
+        reached
+    }
+}
+
+/// Returns the kinds each IDL type belongs to: accounts and events are their own kind, and
+/// every other type is `Types`. Types reachable from an account, event or instruction argument
+/// also take that kind, so that derives on it hold for everything it contains.
+pub(crate) fn type_kinds(idl: &Value) -> HashMap<String, Vec<Kind>> {
+    let items = IdlItems::new(idl);
+    let mut kinds: HashMap<String, Vec<Kind>> = HashMap::new();
+    for type_name in items.type_defs.keys() {
+        if !items.accounts.contains(type_name) && !items.events.contains(type_name) {
+            kinds
+                .entry(type_name.clone())
+                .or_default()
+                .push(Kind::Types);
+        }
+    }
+
+    let reached = [
+        (
+            Kind::Accounts,
+            items.reached_from_types(items.accounts.iter()),
+        ),
+        (Kind::Events, items.reached_from_types(items.events.iter())),
+        (
+            Kind::Args,
+            items.reached_from_instructions(items.instructions.iter()),
+        ),
+    ];
+    for (kind, reached) in reached {
+        for type_name in reached {
+            let entry = kinds.entry(type_name).or_default();
+            if !entry.contains(&kind) {
+                entry.push(kind);
+            }
+        }
+    }
+    kinds
+}
+
+/// Returns the types that are only used by instructions, accounts or events left out by the
+/// filters, and so don't need to be generated
+pub(crate) fn excluded_types(idl: &Value, options: &Options) -> HashSet<String> {
+    let items = IdlItems::new(idl);
+    let instruction_included = |ix: &&Value| {
+        options
+            .instruction_filter
+            .matches(name(ix).unwrap_or_default())
+    };
+    let included = items
+        .reached_from_instructions(items.instructions.iter().filter(instruction_included))
+        .into_iter()
+        .chain(
+            items.reached_from_types(
+                items
+                    .accounts
+                    .iter()
+                    .filter(|a| options.account_filter.matches(a)),
+            ),
+        )
+        .chain(
+            items.reached_from_types(
+                items
+                    .events
+                    .iter()
+                    .filter(|e| options.event_filter.matches(e)),
+            ),
+        )
+        .collect::<HashSet<_>>();
+    items
+        .reached_from_instructions(
+            items
+                .instructions
+                .iter()
+                .filter(|ix| !instruction_included(ix)),
+        )
+        .into_iter()
+        .chain(
+            items.reached_from_types(
+                items
+                    .accounts
+                    .iter()
+                    .filter(|a| !options.account_filter.matches(a)),
+            ),
+        )
+        .chain(
+            items.reached_from_types(
+                items
+                    .events
+                    .iter()
+                    .filter(|e| !options.event_filter.matches(e)),
+            ),
+        )
+        .filter(|type_name| !included.contains(type_name))
+        .collect()
+}
+
+/// Collects the names of the defined types referenced anywhere in `value`, following each one
+/// into its own definition
+pub(crate) fn collect_defined(
+    value: &Value,
+    type_defs: &HashMap<String, Value>,
+    reached: &mut HashSet<String>,
+) {
+    match value {
+        Value::Object(obj) => {
+            if let Some(defined) = obj.get("defined") {
+                let type_name = defined
+                    .get("name")
+                    .or(Some(defined))
+                    .and_then(|v| v.as_str());
+                if let Some(type_name) = type_name {
+                    if let Some(type_def) = type_defs.get(type_name) {
+                        if reached.insert(type_name.to_string()) {
+                            collect_defined(type_def, type_defs, reached);
+                        }
+                    }
+                }
+            }
+            for (key, value) in obj {
+                if key != "defined" && key != "docs" {
+                    collect_defined(value, type_defs, reached);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_defined(value, type_defs, reached);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_patterns() {
+        assert!(glob_match("swap", "swap"));
+        assert!(!glob_match("swap", "swap_v2"));
+        assert!(!glob_match("swap_v2", "swap"));
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "swap"));
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "swap"));
+        assert!(glob_match("swap*", "swap"));
+        assert!(glob_match("swap*", "swap_v2"));
+        assert!(glob_match("*_v2", "swap_v2"));
+        assert!(glob_match("s?ap", "swap"));
+        assert!(!glob_match("s?ap", "sap"));
+        assert!(!glob_match("*_v2", "swap_v3"));
+    }
+
+    #[test]
+    fn star_backtracks() {
+        // The first `_` after `*` is a false start, so `*` has to take more of the name.
+        assert!(glob_match("*_v2", "swap_exact_v2"));
+        assert!(glob_match("a*b*c", "abxbyc"));
+        assert!(glob_match("*a?c", "abcabc"));
+        assert!(glob_match("**x", "yyx"));
+        assert!(!glob_match("a*b*c", "abxbyd"));
+        assert!(!glob_match("*a?c", "abcab"));
+    }
+
+    #[test]
+    fn include_and_exclude() {
+        let filter = Filter {
+            include: Some(vec!["swap*".to_string(), "deposit".to_string()]),
+            exclude: vec!["*_v2".to_string()],
+        };
+        assert!(filter.matches("swap"));
+        assert!(filter.matches("deposit"));
+        assert!(!filter.matches("swap_v2"));
+        assert!(!filter.matches("withdraw"));
+        assert!(!Filter::none().matches("swap"));
+        assert!(Filter::default().matches("swap"));
+    }
+}
@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+
+use quote::quote;
+use serde_json::Value;
+
+use crate::idl::{account_const, account_field, flat_accounts, list, name};
+
+/// PDA code generated for one instruction
+pub struct InstructionPdas {
+    /// `find_<account>_address` functions, for the instruction's accounts struct
+    pub find_fns: Vec<proc_macro2::TokenStream>,
+    /// Statements that re-derive each PDA and push a `ConstraintViolation` if the key passed
+    /// doesn't match, for the instruction's arm of `DecodedInstruction::verify_pdas`. They expect
+    /// `accounts`, `account_data`, `violations` and, if `uses_args`, `args` in scope.
+    pub checks: Vec<proc_macro2::TokenStream>,
+    pub uses_args: bool,
+    pub uses_account_data: bool,
+    /// The accounts that got a `find_<account>_address` function
+    pub derivations: Vec<PdaDerivation>,
+}
+
+/// How to derive one PDA account of an instruction
+pub struct PdaDerivation {
+    pub account: String,
+    pub fn_ident: syn::Ident,
+    /// Where each parameter of the function comes from, and whether it's taken by value
+    pub params: Vec<(Source, bool)>,
+}
+
+/// Where the value of a seed comes from
+#[derive(Clone)]
+pub enum Source {
+    /// The key of another account of the instruction
+    Account(String),
+    /// A field stored in another account's data
+    AccountField { account: String, field: String },
+    /// An instruction argument, or a field nested in one
+    Arg(Vec<String>),
+}
+
+/// A seed that isn't a constant, which becomes a parameter of the `find_<account>_address`
+/// function
+struct SeedParam {
+    ident: syn::Ident,
+    ty: proc_macro2::TokenStream,
+    /// Expression converting the parameter to seed bytes
+    bytes: proc_macro2::TokenStream,
+    /// Whether the parameter is taken by value rather than by reference
+    by_value: bool,
+    is_pubkey: bool,
+    /// The seed's kind and path, which identify the value
+    key: String,
+    source: Source,
+}
+
+enum Seed {
+    Const(Vec<u8>),
+    Param(usize),
+}
+
+/// Generates the PDA functions and checks for the accounts of an instruction that have `pda`
+/// seeds in the IDL.
+///
+/// Seeds can be constants, the keys of other accounts, fields of other accounts' data, or
+/// instruction arguments (and fields nested in them), and the PDA can belong to another program
+/// given by a constant, an account or an argument. Accounts with seeds this crate can't turn
+/// into bytes (e.g. a defined type used as a seed) get no function.
+pub fn instruction_pdas(
+    instruction: &Value,
+    accounts_struct: &syn::Ident,
+    type_defs: &HashMap<&str, &Value>,
+) -> InstructionPdas {
+    let mut pdas = InstructionPdas {
+        find_fns: Vec::new(),
+        checks: Vec::new(),
+        uses_args: false,
+        uses_account_data: false,
+        derivations: Vec::new(),
+    };
+    // Accounts in composite groups are named `<group>.<account>`, as seeds refer to them.
+    let accounts = flat_accounts(instruction);
+    let account_names: Vec<&str> = accounts
+        .iter()
+        .map(|(account_name, _)| account_name.as_str())
+        .collect();
+
+    for (account_name, account) in &accounts {
+        let Some(pda) = account.get("pda") else {
+            continue;
+        };
+        let account_name = account_name.as_str();
+        let group = account_name
+            .rfind('.')
+            .map_or("", |dot| &account_name[..=dot]);
+        let mut params: Vec<SeedParam> = Vec::new();
+        let mut resolve = |seed: &Value| -> Option<Seed> {
+            if seed.get("kind")?.as_str()? == "const" {
+                let bytes = seed.get("value")?.as_array()?;
+                return bytes
+                    .iter()
+                    .map(|b| b.as_u64().filter(|b| *b <= u8::MAX as u64).map(|b| b as u8))
+                    .collect::<Option<Vec<u8>>>()
+                    .map(Seed::Const);
+            }
+            let param = seed_param(seed, instruction, group, &account_names, type_defs)?;
+            match params.iter().position(|p| p.ident == param.ident) {
+                Some(index) if params[index].key == param.key => Some(Seed::Param(index)),
+                // Two different values would need the same parameter name.
+                Some(_) => None,
+                None => {
+                    params.push(param);
+                    Some(Seed::Param(params.len() - 1))
+                }
+            }
+        };
+
+        let Some(seeds) = list(pda, "seeds")
+            .iter()
+            .map(&mut resolve)
+            .collect::<Option<Vec<Seed>>>()
+        else {
+            continue;
+        };
+        let program = match pda.get("program") {
+            Some(program) => match resolve(program) {
+                Some(program) => Some(program),
+                None => continue,
+            },
+            None => None,
+        };
+
+        let seed_exprs: Vec<proc_macro2::TokenStream> = seeds
+            .iter()
+            .map(|seed| match seed {
+                Seed::Const(bytes) => quote! { &[ #( #bytes ),* ] },
+                Seed::Param(index) => params[*index].bytes.clone(),
+            })
+            .collect();
+        let program_expr = match &program {
+            None => quote! { &ID },
+            Some(Seed::Const(bytes)) if bytes.len() == 32 => {
+                quote! { &::solana_sdk::pubkey::Pubkey::new_from_array([ #( #bytes ),* ]) }
+            }
+            Some(Seed::Param(index)) if params[*index].is_pubkey => {
+                let ident = &params[*index].ident;
+                quote! { #ident }
+            }
+            Some(_) => continue,
+        };
+
+        let fn_ident = syn::Ident::new(
+            &format!("find_{}_address", account_field(account_name)),
+            proc_macro2::Span::call_site(),
+        );
+        let param_defs = params.iter().map(|p| {
+            let (ident, ty) = (&p.ident, &p.ty);
+            quote! { #ident: #ty }
+        });
+        let doc = format!(
+            " Derives the address of the `{}` PDA, returning it with its bump seed",
+            account_name
+        );
+        pdas.find_fns.push(quote! {
+            #[doc = #doc]
+            pub fn #fn_ident( #( #param_defs ),* ) -> (::solana_sdk::pubkey::Pubkey, u8) {
+                ::solana_sdk::pubkey::Pubkey::find_program_address(&[ #( #seed_exprs ),* ], #program_expr)
+            }
+        });
+        pdas.derivations.push(PdaDerivation {
+            account: account_name.to_string(),
+            fn_ident: fn_ident.clone(),
+            params: params
+                .iter()
+                .map(|p| (p.source.clone(), p.by_value))
+                .collect(),
+        });
+
+        // The check re-derives the PDA from the instruction's own accounts and args. Fields of
+        // other accounts can only be read when they hold addresses.
+        if params
+            .iter()
+            .any(|p| matches!(p.source, Source::AccountField { .. }) && !p.is_pubkey)
+        {
+            continue;
+        }
+        let account_index = account_const(account_name);
+        let mut values = Vec::new();
+        let mut bindings = Vec::new();
+        let mut call_args = Vec::new();
+        for param in &params {
+            let ident = &param.ident;
+            let (value, call_arg) = match &param.source {
+                Source::Account(account) => {
+                    let index = account_const(account);
+                    (
+                        quote! { accounts.get(#accounts_struct::#index) },
+                        quote! { #ident },
+                    )
+                }
+                Source::AccountField { account, field } => {
+                    pdas.uses_account_data = true;
+                    let index = account_const(account);
+                    (
+                        quote! {
+                            accounts
+                                .get(#accounts_struct::#index)
+                                .and_then(|key| decode_account(account_data(key)?)?.pubkey_field(#field))
+                        },
+                        quote! { &#ident },
+                    )
+                }
+                Source::Arg(path) => {
+                    pdas.uses_args = true;
+                    let path = path
+                        .iter()
+                        .map(|part| syn::Ident::new(part, proc_macro2::Span::call_site()));
+                    let call_arg = if param.by_value {
+                        quote! { *#ident }
+                    } else {
+                        quote! { #ident }
+                    };
+                    (quote! { Some(&args #( .#path )*) }, call_arg)
+                }
+            };
+            values.push(value);
+            bindings.push(quote! { Some(#ident) });
+            call_args.push(call_arg);
+        }
+        pdas.checks.push(quote! {
+            if let (Some(actual), #( #bindings, )*) = (accounts.get(#accounts_struct::#account_index), #( #values, )*) {
+                let (expected, _) = #accounts_struct::#fn_ident( #( #call_args ),* );
+                if expected != *actual {
+                    violations.push(::anchor_decoder_runtime::ConstraintViolation::PdaMismatch {
+                        name: #account_name,
+                        expected,
+                        actual: *actual,
+                    });
+                }
+            }
+        });
+    }
+    pdas
+}
+
+/// Resolves a non-constant seed to a parameter, or `None` if its source or type isn't supported.
+/// Seeds of an account in a composite group name its siblings, so account paths are looked up in
+/// `group` (the `<group>.` prefix, or empty) before the top-level accounts.
+fn seed_param(
+    seed: &Value,
+    instruction: &Value,
+    group: &str,
+    account_names: &[&str],
+    type_defs: &HashMap<&str, &Value>,
+) -> Option<SeedParam> {
+    let kind = seed.get("kind")?.as_str()?;
+    let key = format!("{}:{}", kind, seed.get("path")?.as_str()?);
+    let path: Vec<String> = seed
+        .get("path")?
+        .as_str()?
+        .split('.')
+        .map(str::to_string)
+        .collect();
+    let (source, ty) = match kind {
+        "account" => {
+            let find_account = |path: &str| {
+                [format!("{}{}", group, path), path.to_string()]
+                    .into_iter()
+                    .find(|account| account_names.contains(&account.as_str()))
+            };
+            let full_path = path.join(".");
+            if let Some(account) = find_account(&full_path) {
+                (Source::Account(account), Value::from("pubkey"))
+            } else {
+                let (account, field) = full_path.rsplit_once('.')?;
+                let account = find_account(account)?;
+                let type_name = seed.get("account")?.as_str()?;
+                let ty = field_type(type_defs.get(type_name)?, field)?;
+                (
+                    Source::AccountField {
+                        account,
+                        field: field.to_string(),
+                    },
+                    ty,
+                )
+            }
+        }
+        "arg" => {
+            let arg = list(instruction, "args")
+                .iter()
+                .find(|arg| name(arg) == Some(path[0].as_str()))?;
+            let mut ty = arg.get("type")?.clone();
+            for field in &path[1..] {
+                let type_name = ty.get("defined")?.get("name")?.as_str()?;
+                ty = field_type(type_defs.get(type_name)?, field)?;
+            }
+            (Source::Arg(path.clone()), ty)
+        }
+        _ => return None,
+    };
+
+    let ident = syn::Ident::new(&path.join("_"), proc_macro2::Span::call_site());
+    let is_pubkey = ty == "pubkey";
+    let (ty, bytes, by_value) = match &ty {
+        Value::String(s) => match s.as_str() {
+            "pubkey" => (
+                quote! { &::solana_sdk::pubkey::Pubkey },
+                quote! { #ident.as_ref() },
+                false,
+            ),
+            "string" => (quote! { &str }, quote! { #ident.as_bytes() }, false),
+            "bytes" => (quote! { &[u8] }, quote! { #ident }, false),
+            "bool" => (quote! { bool }, quote! { &[#ident as u8] }, true),
+            "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128" => {
+                let int = syn::Ident::new(s, proc_macro2::Span::call_site());
+                (quote! { #int }, quote! { &#ident.to_le_bytes() }, true)
+            }
+            _ => return None,
+        },
+        ty => match ty
+            .get("array")
+            .and_then(|v| v.as_array())
+            .map(Vec::as_slice)
+        {
+            Some([inner, len]) if *inner == "u8" => {
+                let len = len.as_u64()? as usize;
+                (quote! { &[u8; #len] }, quote! { &#ident[..] }, false)
+            }
+            _ => return None,
+        },
+    };
+    Some(SeedParam {
+        ident,
+        ty,
+        bytes,
+        by_value,
+        is_pubkey,
+        key,
+        source,
+    })
+}
+
+/// Returns the type of a field of a struct type definition
+fn field_type(type_def: &Value, field: &str) -> Option<Value> {
+    list(type_def.get("type")?, "fields")
+        .iter()
+        .find(|f| name(f) == Some(field))?
+        .get("type")
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn sources(
+        instruction: &Value,
+        type_defs: &HashMap<&str, &Value>,
+    ) -> Vec<(String, Vec<String>)> {
+        let accounts_struct = syn::Ident::new("Accounts", proc_macro2::Span::call_site());
+        instruction_pdas(instruction, &accounts_struct, type_defs)
+            .derivations
+            .iter()
+            .map(|derivation| {
+                let params = derivation
+                    .params
+                    .iter()
+                    .map(|(source, _)| match source {
+                        Source::Account(account) => account.clone(),
+                        Source::AccountField { account, field } => format!("{}:{}", account, field),
+                        Source::Arg(path) => format!("arg:{}", path.join(".")),
+                    })
+                    .collect();
+                (derivation.account.clone(), params)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn grouped_seeds_resolve_within_their_group() {
+        let seed = |path: &str| json!({ "kind": "account", "path": path, "account": "Pool" });
+        let instruction = json!({
+            "name": "swap",
+            "accounts": [
+                { "name": "owner" },
+                { "name": "pool" },
+                {
+                    "name": "trade",
+                    "accounts": [
+                        { "name": "owner" },
+                        { "name": "escrow", "pda": { "seeds": [seed("owner"), seed("pool")] } },
+                        { "name": "vault", "pda": { "seeds": [seed("escrow"), seed("pool.mint")] } },
+                    ],
+                },
+                { "name": "config", "pda": { "seeds": [seed("owner"), seed("trade.owner")] } },
+            ],
+            "args": [],
+        });
+        let pool = json!({
+            "name": "Pool",
+            "type": { "kind": "struct", "fields": [{ "name": "mint", "type": "pubkey" }] },
+        });
+        let type_defs = HashMap::from([("Pool", &pool)]);
+        assert_eq!(
+            sources(&instruction, &type_defs),
+            [
+                (
+                    "trade.escrow".to_string(),
+                    vec!["trade.owner".to_string(), "pool".to_string()]
+                ),
+                (
+                    "trade.vault".to_string(),
+                    vec!["trade.escrow".to_string(), "pool:mint".to_string()]
+                ),
+                (
+                    "config".to_string(),
+                    vec!["owner".to_string(), "trade.owner".to_string()]
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use quote::quote;
+use serde_json::Value;
+
+use crate::idl::{ident, name};
+
+/// Generates `summarize_fields` for a struct with named fields, writing them as `name=value`
+/// pairs, and for IDL types (`nested`) an `anchor_decoder_runtime::Summarize` impl that wraps
+/// them in braces, as the struct appears inside another value.
+pub fn struct_summary(
+    type_ident: &syn::Ident,
+    fields: &[Value],
+    nested: bool,
+) -> proc_macro2::TokenStream {
+    let names: Vec<&str> = fields.iter().filter_map(name).collect();
+    let body = if names.is_empty() {
+        quote! {
+            let _ = (self, out, verbosity);
+            Ok(())
+        }
+    } else {
+        let writes = field_writes(names.iter().map(|field| {
+            let field_ident = ident(field);
+            (*field, quote! { &self.#field_ident })
+        }));
+        quote! {
+            #writes
+            Ok(())
+        }
+    };
+    let summarize = nested.then(|| {
+        quote! {
+            impl ::anchor_decoder_runtime::Summarize for #type_ident {
+                fn summarize(&self, out: &mut dyn ::std::fmt::Write, verbosity: ::anchor_decoder_runtime::Verbosity) -> ::std::fmt::Result {
+                    if verbosity == ::anchor_decoder_runtime::Verbosity::Brief {
+                        return out.write_str("{..}");
+                    }
+                    out.write_str("{")?;
+                    self.summarize_fields(out, verbosity)?;
+                    out.write_str("}")
+                }
+            }
+        }
+    });
+    quote! {
+        impl #type_ident {
+            /// Writes the fields as `name=value` pairs, as they appear in summaries
+            pub fn summarize_fields(&self, out: &mut dyn ::std::fmt::Write, verbosity: ::anchor_decoder_runtime::Verbosity) -> ::std::fmt::Result {
+                #body
+            }
+        }
+
+        #summarize
+    }
+}
+
+/// Generates the `anchor_decoder_runtime::Summarize` impl of an enum IDL type. Variants are
+/// written by name, followed by their fields in braces or parentheses.
+pub fn enum_summary(type_ident: &syn::Ident, variants: &[Value]) -> proc_macro2::TokenStream {
+    let arms: Vec<proc_macro2::TokenStream> = variants
+        .iter()
+        .filter_map(|variant| {
+            let variant_name = name(variant)?;
+            let variant_ident = ident(variant_name);
+            let fields = variant
+                .get("fields")
+                .and_then(|v| v.as_array())
+                .filter(|fields| !fields.is_empty());
+            // Fields are bound to positional names, since IDL names could shadow `out`.
+            Some(match fields {
+                Some(fields) if fields[0].get("name").is_some() => {
+                    let names: Vec<&str> = fields.iter().filter_map(name).collect();
+                    let field_idents = names.iter().map(|field| ident(field));
+                    let bindings: Vec<syn::Ident> =
+                        (0..names.len()).map(|i| ident(&format!("field_{}", i))).collect();
+                    let writes = field_writes(
+                        names
+                            .iter()
+                            .zip(&bindings)
+                            .map(|(field, binding)| (*field, quote! { #binding })),
+                    );
+                    quote! {
+                        #type_ident::#variant_ident { #( #field_idents: #bindings ),* } => {
+                            out.write_str(#variant_name)?;
+                            if verbosity == ::anchor_decoder_runtime::Verbosity::Brief {
+                                return out.write_str("{..}");
+                            }
+                            out.write_str("{")?;
+                            #writes
+                            out.write_str("}")
+                        }
+                    }
+                }
+                Some(fields) => {
+                    let bindings: Vec<syn::Ident> =
+                        (0..fields.len()).map(|i| ident(&format!("field_{}", i))).collect();
+                    let writes = bindings.iter().enumerate().map(|(i, binding)| {
+                        let separator = (i > 0).then(|| quote! { out.write_str(", ")?; });
+                        quote! {
+                            #separator
+                            ::anchor_decoder_runtime::Summarize::summarize(#binding, out, verbosity)?;
+                        }
+                    });
+                    quote! {
+                        #type_ident::#variant_ident( #( #bindings ),* ) => {
+                            out.write_str(#variant_name)?;
+                            if verbosity == ::anchor_decoder_runtime::Verbosity::Brief {
+                                return out.write_str("(..)");
+                            }
+                            out.write_str("(")?;
+                            #( #writes )*
+                            out.write_str(")")
+                        }
+                    }
+                }
+                None => quote! {
+                    #type_ident::#variant_ident => out.write_str(#variant_name)
+                },
+            })
+        })
+        .collect();
+    let body = if arms.is_empty() {
+        quote! {
+            let _ = (out, verbosity);
+            match *self {}
+        }
+    } else {
+        // Only variants with fields look at the verbosity.
+        let has_fields = variants.iter().any(|variant| {
+            variant
+                .get("fields")
+                .and_then(|v| v.as_array())
+                .is_some_and(|fields| !fields.is_empty())
+        });
+        let unused = (!has_fields).then(|| quote! { let _ = verbosity; });
+        quote! {
+            #unused
+            match self {
+                #( #arms, )*
+            }
+        }
+    };
+    quote! {
+        impl ::anchor_decoder_runtime::Summarize for #type_ident {
+            fn summarize(&self, out: &mut dyn ::std::fmt::Write, verbosity: ::anchor_decoder_runtime::Verbosity) -> ::std::fmt::Result {
+                #body
+            }
+        }
+    }
+}
+
+/// Generates the `Summarize` and `Display` impls of one of the decoded enums from the
+/// `match self` arms writing each variant. Enums without variants get an empty match.
+pub fn decoded_summary(
+    enum_ident: &syn::Ident,
+    arms: &[proc_macro2::TokenStream],
+) -> proc_macro2::TokenStream {
+    let body = if arms.is_empty() {
+        quote! {
+            let _ = (out, verbosity);
+            match *self {}
+        }
+    } else {
+        quote! {
+            match self {
+                #( #arms, )*
+            }
+        }
+    };
+    quote! {
+        impl ::anchor_decoder_runtime::Summarize for #enum_ident {
+            fn summarize(&self, out: &mut dyn ::std::fmt::Write, verbosity: ::anchor_decoder_runtime::Verbosity) -> ::std::fmt::Result {
+                #body
+            }
+        }
+
+        /// Writes the summary at the default verbosity
+        impl ::std::fmt::Display for #enum_ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::anchor_decoder_runtime::Summarize::summarize(self, f, ::anchor_decoder_runtime::Verbosity::default())
+            }
+        }
+    }
+}
+
+/// Generates the arm of a decoded enum's summary for a variant holding `value`, written as its
+/// IDL name followed by the value's fields in parentheses. Values of types without named fields
+/// are written whole.
+pub fn variant_summary_arm(
+    pattern: proc_macro2::TokenStream,
+    name: &str,
+    has_fields: bool,
+) -> proc_macro2::TokenStream {
+    let write_value = if has_fields {
+        quote! { value.summarize_fields(out, verbosity)?; }
+    } else {
+        quote! { ::anchor_decoder_runtime::Summarize::summarize(value, out, verbosity)?; }
+    };
+    let open = format!("{}(", name);
+    quote! {
+        #pattern => {
+            out.write_str(#open)?;
+            #write_value
+            out.write_str(")")
+        }
+    }
+}
+
+/// Statements writing `name=value` pairs separated by commas, given each field's name and an
+/// expression referencing its value
+fn field_writes<'a>(
+    fields: impl Iterator<Item = (&'a str, proc_macro2::TokenStream)>,
+) -> proc_macro2::TokenStream {
+    let writes = fields.enumerate().map(|(i, (field, value))| {
+        let label = if i == 0 {
+            format!("{}=", field)
+        } else {
+            format!(", {}=", field)
+        };
+        quote! {
+            out.write_str(#label)?;
+            ::anchor_decoder_runtime::Summarize::summarize(#value, out, verbosity)?;
+        }
+    });
+    quote! { #( #writes )* }
+}
@@ -0,0 +1,608 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::discriminator::{
+    overlapping_discriminators, read_discriminator, EMIT_CPI_INSTRUCTION_DISCRIMINATOR,
+};
+use crate::idl::{account_const_name, display_name, flat_accounts, list, name};
+use crate::names::GeneratedNames;
+use crate::{to_camel_case, to_snake_case};
+
+/// A problem found in an IDL by [`validate_idl`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdlError {
+    /// Where the problem is, e.g. "instruction `swap`, arg `amount`"
+    pub item: String,
+    pub message: String,
+}
+
+impl fmt::Display for IdlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.item, self.message)
+    }
+}
+
+impl std::error::Error for IdlError {}
+
+/// Checks an IDL before generating code from it, returning every problem found rather than
+/// stopping at the first:
+///  - required fields (program address and metadata, names, discriminators, argument lists), and
+///    well-formed program and fixed account addresses
+///  - names that aren't valid Rust identifiers, and duplicate names
+///  - `defined` types that don't exist, types this crate can't generate, and accounts or events
+///    without a matching type
+///  - discriminators that overlap within instructions (including emit_cpi), accounts or events
+///  - generated names that still collide after falling back to longer names, e.g. types named
+///    both `Decoder` and `AnchorDecoder`, and instructions or instruction accounts whose names only differ in case or
+///    underscores, which would share a variant or an index constant
+///
+/// [`generate`](crate::generate) runs this first and panics with the full list of problems.
+pub fn validate_idl(idl: &Value) -> Result<(), Vec<IdlError>> {
+    let mut v = Validator::default();
+
+    match idl.get("address").and_then(|v| v.as_str()) {
+        Some(address) if is_base58_address(address) => {}
+        Some(address) => v.error("program", format!("invalid program address `{}`", address)),
+        None => v.error("program", "missing program address"),
+    }
+    for key in ["name", "version", "spec"] {
+        if idl
+            .get("metadata")
+            .and_then(|m| m.get(key))
+            .and_then(|v| v.as_str())
+            .is_none()
+        {
+            v.error("metadata", format!("missing `{}`", key));
+        }
+    }
+
+    let types = list(idl, "types");
+    let type_names: HashSet<&str> = types.iter().filter_map(name).collect();
+    for type_def in types {
+        v.check_type_def(type_def, &type_names);
+    }
+    v.check_unique("type", types);
+
+    match idl.get("instructions").and_then(|v| v.as_array()) {
+        Some(instructions) => {
+            for instruction in instructions {
+                v.check_instruction(instruction, &type_names);
+            }
+            v.check_unique("instruction", instructions);
+        }
+        None => v.error("program", "missing `instructions` array"),
+    }
+
+    for kind in ["account", "event"] {
+        let items = list(idl, &format!("{}s", kind));
+        for item in items {
+            let item_name = v.check_name(kind, item);
+            if !type_names.contains(item_name.as_str()) {
+                v.error(
+                    format!("{} `{}`", kind, item_name),
+                    "no type with this name in `types`",
+                );
+            }
+        }
+        v.check_unique(kind, items);
+    }
+
+    v.check_discriminators(idl);
+    v.check_generated_names(idl);
+
+    if v.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(v.errors)
+    }
+}
+
+#[derive(Default)]
+struct Validator {
+    errors: Vec<IdlError>,
+}
+
+impl Validator {
+    fn error(&mut self, item: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(IdlError {
+            item: item.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Checks that an item has a name that's a valid identifier, returning the name
+    fn check_name(&mut self, kind: &str, item: &Value) -> String {
+        match name(item) {
+            Some(item_name) => {
+                if syn::parse_str::<syn::Ident>(item_name).is_err() {
+                    self.error(
+                        format!("{} `{}`", kind, item_name),
+                        "name is not a valid Rust identifier",
+                    );
+                }
+                item_name.to_string()
+            }
+            None => {
+                self.error(kind, "missing name");
+                "unknown".to_string()
+            }
+        }
+    }
+
+    fn check_unique(&mut self, kind: &str, items: &[Value]) {
+        let mut seen = HashSet::new();
+        for item_name in items.iter().filter_map(name) {
+            if !seen.insert(item_name) {
+                self.error(
+                    format!("{} `{}`", kind, item_name),
+                    "defined more than once",
+                );
+            }
+        }
+    }
+
+    fn check_instruction(&mut self, instruction: &Value, type_names: &HashSet<&str>) {
+        let instruction_name = self.check_name("instruction", instruction);
+        let item = format!("instruction `{}`", instruction_name);
+
+        match instruction.get("args").and_then(|v| v.as_array()) {
+            Some(args) => {
+                for arg in args {
+                    let arg_name = self.check_name(&format!("{}, arg", item), arg);
+                    self.check_type(
+                        &format!("{}, arg `{}`", item, arg_name),
+                        arg.get("type").unwrap_or(&Value::Null),
+                        type_names,
+                    );
+                }
+                self.check_unique(&format!("{}, arg", item), args);
+            }
+            None => self.error(item.clone(), "missing `args` array"),
+        }
+
+        // Each account gets a field, a constant and a lookup by name, so they must be unique.
+        // Accounts inside composite groups are flattened to `<group>.<account>`.
+        let accounts = flat_accounts(instruction);
+        let mut seen = HashSet::new();
+        for (account_name, account) in &accounts {
+            if name(account).is_none() {
+                self.error(format!("{}, account", item), "missing name");
+            } else if syn::parse_str::<syn::Ident>(&account_name.replace('.', "_")).is_err() {
+                self.error(
+                    format!("{}, account `{}`", item, account_name),
+                    "name is not a valid Rust identifier",
+                );
+            }
+            if !seen.insert(account_name) {
+                self.error(
+                    format!("{}, account `{}`", item, account_name),
+                    "defined more than once",
+                );
+            }
+            match account.get("address") {
+                Some(Value::String(address)) if is_base58_address(address) => {}
+                Some(address) if !address.is_null() => self.error(
+                    format!("{}, account `{}`", item, account_name),
+                    format!("invalid fixed address {}", address),
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    fn check_type_def(&mut self, type_def: &Value, type_names: &HashSet<&str>) {
+        let type_name = self.check_name("type", type_def);
+        let item = format!("type `{}`", type_name);
+        if type_def
+            .get("generics")
+            .is_some_and(|g| g.as_array().is_some_and(|g| !g.is_empty()))
+        {
+            self.error(item.clone(), "generic types are not supported");
+        }
+
+        let type_info = type_def.get("type").unwrap_or(&Value::Null);
+        match type_info.get("kind").and_then(|v| v.as_str()) {
+            Some("struct") => {
+                let fields = list(type_info, "fields");
+                self.check_fields(&item, fields, type_names);
+            }
+            Some("enum") => {
+                let variants = list(type_info, "variants");
+                for variant in variants {
+                    let variant_name = self.check_name(&format!("{}, variant", item), variant);
+                    let fields = list(variant, "fields");
+                    self.check_fields(
+                        &format!("{}, variant `{}`", item, variant_name),
+                        fields,
+                        type_names,
+                    );
+                }
+                self.check_unique(&format!("{}, variant", item), variants);
+            }
+            Some(kind) => self.error(item, format!("unsupported type kind `{}`", kind)),
+            None => self.error(item, "missing type kind"),
+        }
+    }
+
+    /// Checks named fields, or the bare types of a tuple struct or variant
+    fn check_fields(&mut self, item: &str, fields: &[Value], type_names: &HashSet<&str>) {
+        let named = fields.iter().all(|f| f.get("name").is_some());
+        for field in fields {
+            if named {
+                let field_name = self.check_name(&format!("{}, field", item), field);
+                self.check_type(
+                    &format!("{}, field `{}`", item, field_name),
+                    field.get("type").unwrap_or(&Value::Null),
+                    type_names,
+                );
+            } else {
+                self.check_type(item, field, type_names);
+            }
+        }
+        if named {
+            self.check_unique(&format!("{}, field", item), fields);
+        }
+    }
+
+    /// Checks that a type is one the generator maps to Rust, and that `defined` types exist
+    fn check_type(&mut self, item: &str, ty: &Value, type_names: &HashSet<&str>) {
+        if let Some(s) = ty.as_str() {
+            if !matches!(
+                s,
+                "u8" | "u16"
+                    | "u32"
+                    | "u64"
+                    | "u128"
+                    | "i8"
+                    | "i16"
+                    | "i32"
+                    | "i64"
+                    | "i128"
+                    | "f32"
+                    | "f64"
+                    | "bool"
+                    | "pubkey"
+                    | "string"
+                    | "bytes"
+            ) {
+                self.error(item, format!("unsupported type `{}`", s));
+            }
+            return;
+        }
+        if let Some(inner) = ty.get("vec").or_else(|| ty.get("option")) {
+            return self.check_type(item, inner, type_names);
+        }
+        if let Some(array) = ty.get("array") {
+            match array.as_array().map(Vec::as_slice) {
+                Some([inner, len]) if len.is_u64() => self.check_type(item, inner, type_names),
+                _ => self.error(item, format!("unsupported array type {}", array)),
+            }
+            return;
+        }
+        if let Some(defined) = ty.get("defined") {
+            match defined.get("name").and_then(|v| v.as_str()) {
+                Some(type_name) if type_names.contains(type_name) => {}
+                Some(type_name) => self.error(item, format!("undefined type `{}`", type_name)),
+                None => self.error(item, format!("unsupported type reference {}", defined)),
+            }
+            return;
+        }
+        self.error(item, format!("unsupported type {}", ty));
+    }
+
+    fn check_discriminators(&mut self, idl: &Value) {
+        for kind in ["instruction", "account", "event"] {
+            let mut discriminators = Vec::new();
+            for item in list(idl, &format!("{}s", kind)) {
+                let item_name = name(item).unwrap_or("unknown");
+                match read_discriminator(item) {
+                    Ok(discriminator) => {
+                        discriminators.push((item_name.to_string(), discriminator))
+                    }
+                    Err(message) => self.error(format!("{} `{}`", kind, item_name), message),
+                }
+            }
+            // Events emitted through emit_cpi share the instruction namespace.
+            if kind == "instruction" && !list(idl, "events").is_empty() {
+                discriminators.push((
+                    "emit_cpi".to_string(),
+                    EMIT_CPI_INSTRUCTION_DISCRIMINATOR.to_vec(),
+                ));
+            }
+            for (a, b) in overlapping_discriminators(&discriminators) {
+                let ((a_name, a), (b_name, b)) = (&discriminators[a], &discriminators[b]);
+                self.error(
+                    format!("{} `{}`", kind, a_name),
+                    format!("discriminator {:?} overlaps with `{}` {:?}", a, b_name, b),
+                );
+            }
+        }
+    }
+
+    /// Field names are snake_cased for the methods and constants generated per field (view
+    /// accessors, `filter_by_<field>`, `<FIELD>_OFFSET`), so names that only differ in case would
+    /// collide
+    fn check_snake_case_fields(&mut self, item: &str, fields: &[Value]) {
+        let mut seen: HashMap<String, &str> = HashMap::new();
+        for field_name in fields.iter().filter_map(name) {
+            let snake = to_snake_case(field_name);
+            if syn::parse_str::<syn::Ident>(&snake).is_err() {
+                self.error(
+                    format!("{} `{}`", item, field_name),
+                    format!("generated name `{}` is not a valid Rust identifier", snake),
+                );
+            }
+            match seen.get(&snake) {
+                Some(existing) if *existing != field_name => self.error(
+                    format!("{} `{}`", item, field_name),
+                    format!("generated name `{}` collides with `{}`", snake, existing),
+                ),
+                Some(_) => {}
+                None => {
+                    seen.insert(snake, field_name);
+                }
+            }
+        }
+    }
+
+    /// Checks that the names of generated items don't collide with each other or with the items
+    /// generated for every IDL, once instruction structs and account views have fallen back to
+    /// their longer names
+    fn check_generated_names(&mut self, idl: &Value) {
+        for (origin, ident, existing) in GeneratedNames::new(idl).collisions {
+            self.error(
+                origin,
+                format!("generated name `{}` collides with {}", ident, existing),
+            );
+        }
+
+        // Names that only differ in case or underscores map to the same variant or constant.
+        // Identical names are reported as duplicates instead.
+        let has_events = !list(idl, "events").is_empty();
+        let mut variants: HashMap<String, &str> = HashMap::new();
+        for instruction in list(idl, "instructions") {
+            let Some(instruction_name) = name(instruction) else {
+                continue;
+            };
+            let origin = format!("instruction `{}`", instruction_name);
+            let variant = to_camel_case(instruction_name);
+            if variant == "Ignored" || (has_events && variant == "EmitCpi") {
+                self.error(
+                    origin.clone(),
+                    format!(
+                        "generated variant `{}` collides with a built-in variant",
+                        variant
+                    ),
+                );
+            }
+            match variants.get(&variant) {
+                Some(existing) if *existing != instruction_name => self.error(
+                    origin.clone(),
+                    format!(
+                        "generated variant `{}` collides with instruction `{}`",
+                        variant, existing
+                    ),
+                ),
+                Some(_) => {}
+                None => {
+                    variants.insert(variant, instruction_name);
+                }
+            }
+
+            let mut consts: HashMap<String, String> = HashMap::new();
+            for (account_name, _) in flat_accounts(instruction) {
+                let account_const = account_const_name(&account_name);
+                match consts.get(&account_const) {
+                    Some(existing) if *existing != account_name => self.error(
+                        format!("{}, account `{}`", origin, account_name),
+                        format!(
+                            "generated constant `{}` collides with account `{}`",
+                            account_const, existing
+                        ),
+                    ),
+                    Some(_) => {}
+                    None => {
+                        consts.insert(account_const, account_name);
+                    }
+                }
+            }
+            self.check_snake_case_fields(&format!("{}, arg", origin), list(instruction, "args"));
+        }
+        for type_def in list(idl, "types") {
+            let fields = type_def
+                .get("type")
+                .map(|t| list(t, "fields"))
+                .unwrap_or_default();
+            self.check_snake_case_fields(
+                &format!("type `{}`, field", display_name(type_def)),
+                fields,
+            );
+        }
+        for kind in ["account", "event"] {
+            for item_name in list(idl, &format!("{}s", kind)).iter().filter_map(name) {
+                if item_name == "Ignored" {
+                    self.error(
+                        format!("{} `{}`", kind, item_name),
+                        "generated variant `Ignored` collides with a built-in variant",
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Checks the shape of a base58 Solana address: 32 to 44 characters from the base58 alphabet
+fn is_base58_address(address: &str) -> bool {
+    (32..=44).contains(&address.len())
+        && address
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l'))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn idl(instructions: Value, types: Value) -> Value {
+        json!({
+            "address": "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc",
+            "metadata": { "name": "test", "version": "0.1.0", "spec": "0.1.0" },
+            "instructions": instructions,
+            "types": types,
+        })
+    }
+
+    fn instruction(name: &str, discriminator: &[u8], accounts: Value) -> Value {
+        json!({ "name": name, "discriminator": discriminator, "accounts": accounts, "args": [] })
+    }
+
+    fn errors(idl: &Value) -> Vec<String> {
+        match validate_idl(idl) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn valid_idl() {
+        let idl = idl(
+            json!([instruction("swap", &[1, 2], json!([{ "name": "pool" }]))]),
+            json!([]),
+        );
+        assert_eq!(errors(&idl), Vec::<String>::new());
+    }
+
+    #[test]
+    fn overlapping_prefixes() {
+        let idl = idl(
+            json!([
+                instruction("swap", &[1], json!([])),
+                instruction("swap_exact", &[1, 2], json!([])),
+                instruction("deposit", &[2, 1], json!([])),
+            ]),
+            json!([]),
+        );
+        assert_eq!(
+            errors(&idl),
+            ["instruction `swap`: discriminator [1] overlaps with `swap_exact` [1, 2]"]
+        );
+    }
+
+    #[test]
+    fn missing_defined_type() {
+        let mut swap = instruction("swap", &[1], json!([]));
+        swap["args"] = json!([{ "name": "params", "type": { "defined": { "name": "Params" } } }]);
+        let idl = idl(json!([swap]), json!([]));
+        assert_eq!(
+            errors(&idl),
+            ["instruction `swap`, arg `params`: undefined type `Params`"]
+        );
+    }
+
+    #[test]
+    fn duplicate_instruction_account() {
+        let idl = idl(
+            json!([instruction(
+                "swap",
+                &[1],
+                json!([
+                    { "name": "pool" },
+                    { "name": "trade", "accounts": [{ "name": "pool" }] },
+                    { "name": "pool" },
+                ]),
+            )]),
+            json!([]),
+        );
+        assert_eq!(
+            errors(&idl),
+            ["instruction `swap`, account `pool`: defined more than once"]
+        );
+    }
+
+    #[test]
+    fn colliding_variants() {
+        let idl = idl(
+            json!([
+                instruction("swap_v2", &[1], json!([])),
+                instruction("swapV2", &[2], json!([])),
+            ]),
+            json!([]),
+        );
+        assert_eq!(
+            errors(&idl),
+            ["instruction `swapV2`: generated variant `SwapV2` collides with instruction `swap_v2`"]
+        );
+    }
+
+    #[test]
+    fn colliding_support_item_fallback() {
+        let struct_type =
+            |name: &str| json!({ "name": name, "type": { "kind": "struct", "fields": [] } });
+        let only_decoder = idl(json!([]), json!([struct_type("Decoder")]));
+        assert_eq!(errors(&only_decoder), Vec::<String>::new());
+
+        let both = idl(
+            json!([]),
+            json!([struct_type("Decoder"), struct_type("AnchorDecoder")]),
+        );
+        assert_eq!(
+            errors(&both),
+            ["type `Decoder`: generated name `AnchorDecoder` collides with type `AnchorDecoder`"]
+        );
+    }
+
+    #[test]
+    fn colliding_snake_case_fields() {
+        let mut swap = instruction("swap", &[1], json!([]));
+        swap["args"] = json!([
+            { "name": "minOut", "type": "u64" },
+            { "name": "min_out", "type": "u64" },
+        ]);
+        let pool = json!({
+            "name": "Pool",
+            "type": {
+                "kind": "struct",
+                "fields": [
+                    { "name": "feeRecipient", "type": "pubkey" },
+                    { "name": "Type", "type": "u8" },
+                ],
+            },
+        });
+        assert_eq!(
+            errors(&idl(json!([swap]), json!([pool]))),
+            [
+                "instruction `swap`, arg `min_out`: generated name `min_out` collides with `minOut`",
+                "type `Pool`, field `Type`: generated name `type` is not a valid Rust identifier",
+            ]
+        );
+    }
+
+    #[test]
+    fn colliding_account_constants() {
+        let idl = idl(
+            json!([instruction(
+                "swap",
+                &[1],
+                json!([
+                    { "name": "a" },
+                    { "name": "A" },
+                    { "name": "trade", "accounts": [{ "name": "pool" }] },
+                    { "name": "trade_pool" },
+                ]),
+            )]),
+            json!([]),
+        );
+        assert_eq!(
+            errors(&idl),
+            [
+                "instruction `swap`, account `A`: generated constant `A` collides with account `a`",
+                "instruction `swap`, account `trade_pool`: generated constant `TRADE_POOL` \
+                 collides with account `trade.pool`",
+            ]
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::{HashMap, HashSet};
+
+use quote::quote;
+use serde_json::Value;
+
+use crate::docs::{program_doc_lines, without_docs};
+use crate::layout::type_sizes;
+use crate::names::GeneratedNames;
+use crate::options::{collect_defined, excluded_types, type_kinds, Kind};
+use crate::summary::decoded_summary;
+use crate::{generate_version, to_camel_case, type_def_tokens, Options};
+
+/// One IDL version of a program, for [`generate_versions`](crate::generate_versions)
+pub struct IdlVersion {
+    /// Name of the module generated for this version, e.g. `v1`
+    pub module: String,
+    pub idl: Value,
+    /// First slot whose data is decoded with this version. Versions must be given in order of
+    /// increasing `from_slot`.
+    pub from_slot: u64,
+}
+
+/// Generates a decoder for each IDL version of a program, in a module per version, along with
+/// `decode_instruction_at`, `decode_account_at` and `decode_event_at` functions that pick the
+/// version by slot or by IDL version.
+///
+/// Types that are identical in every version that defines them (ignoring docs) are generated
+/// once, next to the version modules, and re-exported from each of them, so values can be
+/// compared or moved between versions. Account types always get a type per version, since each
+/// version implements its own discriminator and filters on them.
+///
+/// Like [`generate`](crate::generate), the output doesn't define `ID`; each version module
+/// re-exports the `ID` of the module around it. Panics if no versions are given or if they
+/// aren't in order of increasing `from_slot`. The `DecodedInstruction`, `DecodedAccount` and
+/// `DecodedEvent` enums next to the version modules are named `Anchor<Enum>` if a shared type
+/// takes their name, like those in each version.
+pub fn generate_versions(versions: &[IdlVersion], options: &Options) -> proc_macro2::TokenStream {
+    assert!(!versions.is_empty(), "at least one IDL version is required");
+    for pair in versions.windows(2) {
+        assert!(
+            pair[0].from_slot < pair[1].from_slot,
+            "IDL version `{}` must start at a later slot than `{}`",
+            pair[1].module,
+            pair[0].module
+        );
+    }
+
+    let idls: Vec<&Value> = versions.iter().map(|version| &version.idl).collect();
+    let shared = shared_types(&idls);
+
+    // Generate each shared type once, from the first version that defines it. Kinds are merged
+    // across versions so that derives hold wherever the type is used.
+    let mut shared_defs = Vec::new();
+    let mut emitted = HashSet::new();
+    let kinds: Vec<HashMap<String, Vec<Kind>>> = idls.iter().map(|idl| type_kinds(idl)).collect();
+    let excluded: Vec<HashSet<String>> = idls
+        .iter()
+        .map(|idl| excluded_types(idl, options))
+        .collect();
+    for idl in &idls {
+        let types = idl
+            .get("types")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let generated_types = type_sizes(&types);
+        for type_def in &types {
+            let Some(name) = type_def.get("name").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            if !shared.contains(name) || !emitted.insert(name.to_string()) {
+                continue;
+            }
+            // Only generate it if some version still uses it.
+            if excluded
+                .iter()
+                .zip(&kinds)
+                .all(|(excluded, kinds)| excluded.contains(name) || !kinds.contains_key(name))
+            {
+                continue;
+            }
+            let mut type_kinds: Vec<Kind> = Vec::new();
+            for kind in kinds.iter().filter_map(|kinds| kinds.get(name)).flatten() {
+                if !type_kinds.contains(kind) {
+                    type_kinds.push(*kind);
+                }
+            }
+            shared_defs.extend(type_def_tokens(
+                type_def,
+                &generated_types,
+                &type_kinds,
+                options,
+            ));
+        }
+    }
+
+    let mut modules = Vec::new();
+    let mut version_entries = Vec::new();
+    let mut variants = Vec::new();
+    let mut slot_arms = Vec::new();
+    for (index, version) in versions.iter().enumerate() {
+        let module = syn::Ident::new(&version.module, proc_macro2::Span::call_site());
+        let module_name = &version.module;
+        let variant = syn::Ident::new(&to_camel_case(module_name), proc_macro2::Span::call_site());
+        let from_slot = version.from_slot;
+        let docs = program_doc_lines(&version.idl);
+        let decoder = generate_version(&version.idl, options, &shared);
+        modules.push(quote! {
+            #( #[doc = #docs] )*
+            pub mod #module {
+                pub use super::ID;
+
+                #decoder
+            }
+        });
+        version_entries.push(quote! { (#module_name, #module::PROGRAM_VERSION, #from_slot) });
+        variants.push((module, variant, GeneratedNames::new(&version.idl)));
+        slot_arms.push((index, from_slot));
+    }
+
+    let decoded_attrs = options.attrs(&[]);
+    let decoded_enum = |name: &str, doc: &str| {
+        let enum_name = if shared.contains(name) {
+            format!("Anchor{}", name)
+        } else {
+            name.to_string()
+        };
+        let enum_ident = syn::Ident::new(&enum_name, proc_macro2::Span::call_site());
+        let function = syn::Ident::new(
+            &format!(
+                "decode_{}",
+                name.trim_start_matches("Decoded").to_lowercase()
+            ),
+            proc_macro2::Span::call_site(),
+        );
+        let at_function =
+            syn::Ident::new(&format!("{}_at", function), proc_macro2::Span::call_site());
+        let variant_defs = variants.iter().map(|(module, variant, names)| {
+            let doc = format!(" Decoded with the `{}` IDL", module);
+            let version_enum = names.support(name);
+            quote! {
+                #[doc = #doc]
+                #variant(#module::#version_enum)
+            }
+        });
+        let version_arms = variants.iter().map(|(module, variant, _)| {
+            let module_name = module.to_string();
+            quote! { #enum_ident::#variant(_) => #module_name }
+        });
+        let summary_arms: Vec<proc_macro2::TokenStream> = variants
+            .iter()
+            .map(|(_, variant, _)| {
+                quote! {
+                    #enum_ident::#variant(value) => ::anchor_decoder_runtime::Summarize::summarize(value, out, verbosity)
+                }
+            })
+            .collect();
+        let summary = decoded_summary(&enum_ident, &summary_arms);
+        let decode_arms = variants
+            .iter()
+            .enumerate()
+            .map(|(index, (module, variant, _))| {
+                quote! { #index => #module::#function(data).map(#enum_ident::#variant) }
+            });
+        let at_doc = format!(
+            " Decodes {} data with the IDL version in effect at `at`, a slot or an IDL version",
+            name.trim_start_matches("Decoded").to_lowercase()
+        );
+        quote! {
+            #[doc = #doc]
+            #[derive(Debug)]
+            #decoded_attrs
+            pub enum #enum_ident {
+                #( #variant_defs, )*
+            }
+
+            impl #enum_ident {
+                /// Returns the name of the module for the IDL version the value was decoded with
+                pub fn version(&self) -> &'static str {
+                    match self {
+                        #( #version_arms, )*
+                    }
+                }
+            }
+
+            #summary
+
+            #[doc = #at_doc]
+            pub fn #at_function<'a>(at: impl Into<At<'a>>, data: &[u8]) -> Option<#enum_ident> {
+                match __anchor_decoder::version_index(at.into())? {
+                    #( #decode_arms, )*
+                    _ => None,
+                }
+            }
+        }
+    };
+    let instruction = decoded_enum(
+        "DecodedInstruction",
+        " An instruction decoded with one of the program's IDL versions",
+    );
+    let account = decoded_enum(
+        "DecodedAccount",
+        " An account decoded with one of the program's IDL versions",
+    );
+    let event = decoded_enum(
+        "DecodedEvent",
+        " An event decoded with one of the program's IDL versions",
+    );
+
+    let slot_checks = slot_arms.iter().rev().map(|(index, from_slot)| {
+        quote! {
+            if slot >= #from_slot {
+                return Some(#index);
+            }
+        }
+    });
+
+    quote! {
+        #( #shared_defs )*
+
+        #( #modules )*
+
+        /// IDL versions in order, as the version module name, the program version from the IDL
+        /// metadata and the first slot decoded with it
+        pub const VERSIONS: &[(&str, &str, u64)] = &[
+            #( #version_entries, )*
+        ];
+
+        /// Selects the IDL version to decode with
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum At<'a> {
+            /// The version in effect at this slot
+            Slot(u64),
+            /// The version with this module name or IDL metadata version. If several IDLs share
+            /// a metadata version, the last of them is used.
+            Version(&'a str),
+        }
+
+        impl From<u64> for At<'_> {
+            fn from(slot: u64) -> Self {
+                At::Slot(slot)
+            }
+        }
+
+        impl<'a> From<&'a str> for At<'a> {
+            fn from(version: &'a str) -> Self {
+                At::Version(version)
+            }
+        }
+
+        /// Support functions for the generated items, kept in their own module so they don't
+        /// clash with items in the surrounding module
+        mod __anchor_decoder {
+            use super::*;
+
+            /// Returns the index in `VERSIONS` of the IDL version to decode with
+            pub(super) fn version_index(at: At<'_>) -> Option<usize> {
+                match at {
+                    At::Slot(slot) => {
+                        #( #slot_checks )*
+                        None
+                    }
+                    At::Version(version) => VERSIONS
+                        .iter()
+                        .rposition(|(module, program_version, _)| *module == version || *program_version == version),
+                }
+            }
+        }
+
+        #instruction
+        #account
+        #event
+    }
+}
+
+/// Returns the names of the non-account types that are defined identically, ignoring docs, in
+/// every version that defines them and in at least two versions, and whose nested types are all
+/// shared too
+fn shared_types(idls: &[&Value]) -> HashSet<String> {
+    let type_defs: Vec<HashMap<String, Value>> = idls
+        .iter()
+        .map(|idl| {
+            idl.get("types")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|t| Some((t.get("name")?.as_str()?.to_string(), t.clone())))
+                .collect()
+        })
+        .collect();
+    let accounts: HashSet<&str> = idls
+        .iter()
+        .filter_map(|idl| idl.get("accounts").and_then(|v| v.as_array()))
+        .flatten()
+        .filter_map(|account| account.get("name").and_then(|v| v.as_str()))
+        .collect();
+
+    let mut shared: HashSet<String> = HashSet::new();
+    let names: HashSet<&String> = type_defs.iter().flat_map(|defs| defs.keys()).collect();
+    for name in names {
+        if accounts.contains(name.as_str()) {
+            continue;
+        }
+        let defs: Vec<Value> = type_defs
+            .iter()
+            .filter_map(|defs| defs.get(name))
+            .map(without_docs)
+            .collect();
+        if defs.len() >= 2 && defs.iter().all(|def| *def == defs[0]) {
+            shared.insert(name.clone());
+        }
+    }
+
+    // A type can only be shared if everything it contains is shared.
+    loop {
+        let unshared: Vec<String> = shared
+            .iter()
+            .filter(|name| {
+                type_defs.iter().any(|defs| {
+                    let Some(def) = defs.get(*name) else {
+                        return false;
+                    };
+                    let mut nested = HashSet::new();
+                    collect_defined(def, defs, &mut nested);
+                    nested.iter().any(|nested| !shared.contains(nested))
+                })
+            })
+            .cloned()
+            .collect();
+        if unshared.is_empty() {
+            return shared;
+        }
+        for name in unshared {
+            shared.remove(&name);
+        }
+    }
+}
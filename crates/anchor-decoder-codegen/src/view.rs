@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use quote::quote;
+use serde_json::Value;
+
+use crate::docs::doc_attrs;
+use crate::layout::field_offsets;
+use crate::{map_idl_type, to_snake_case};
+
+/// Generates a borrowed `<Account>View<'a>` type (named `view_ident`) for an account struct. The view wraps the
+/// serialized account bytes and exposes one accessor per field that decodes only that field.
+///
+/// Fields at a static offset are read directly. Past the first variable-size field, offsets are
+/// found at runtime by skipping over each preceding field, adding its static size when it has
+/// one and deserializing it otherwise. A field without a name or type can't be skipped over, so
+/// it and the fields after it get no accessor.
+pub fn account_view(
+    type_ident: &syn::Ident,
+    view_ident: &syn::Ident,
+    account_decode_error: &syn::Ident,
+    fields: &[Value],
+    generated_types: &HashMap<String, Option<usize>>,
+) -> proc_macro2::TokenStream {
+    let mut accessors = Vec::new();
+    let mut offset_fns = Vec::new();
+    // Offset expression and type of the previous field, used to locate fields after the
+    // static prefix.
+    let mut prev: Option<(proc_macro2::TokenStream, &Value)> = None;
+
+    for (field, offset) in fields.iter().zip(field_offsets(fields, generated_types)) {
+        let (Some(field_name), Some(field_type)) = (
+            field.get("name").and_then(|v| v.as_str()),
+            field.get("type"),
+        ) else {
+            break;
+        };
+        // Accessors are methods, so camelCase IDL names are converted to snake_case.
+        let method_name = to_snake_case(field_name);
+        let field_ident = syn::Ident::new(&method_name, proc_macro2::Span::call_site());
+        let field_tokens = map_idl_type(field_type, generated_types).tokens;
+
+        let offset_expr = match (offset, &prev) {
+            (Some(offset), _) => quote! { #offset },
+            (None, Some((prev_offset, prev_type))) => {
+                let offset_fn = syn::Ident::new(
+                    &format!("offset_of_{}", method_name),
+                    proc_macro2::Span::call_site(),
+                );
+                let prev_type = map_idl_type(prev_type, generated_types);
+                let next_offset = match prev_type.size {
+                    Some(size) => quote! { Ok(start + #size) },
+                    None => {
+                        let prev_tokens = prev_type.tokens;
+                        quote! { __anchor_decoder::skip_field::<#prev_tokens>(self.data, start) }
+                    }
+                };
+                offset_fns.push(quote! {
+                    fn #offset_fn(&self) -> ::std::io::Result<usize> {
+                        let start = #prev_offset;
+                        #next_offset
+                    }
+                });
+                quote! { self.#offset_fn()? }
+            }
+            // Only fields after one that couldn't be read lack both; they can't be located.
+            (None, None) => break,
+        };
+
+        let doc = format!(" Decodes only the `{}` field", field_name);
+        let field_docs = doc_attrs(field);
+        let separator = (!field_docs.is_empty()).then(|| quote! { #[doc = ""] });
+        accessors.push(quote! {
+            #[doc = #doc]
+            #separator
+            #field_docs
+            pub fn #field_ident(&self) -> ::std::io::Result<#field_tokens> {
+                __anchor_decoder::view_field::<#field_tokens>(self.data, #offset_expr)
+            }
+        });
+
+        prev = Some((offset_expr, field_type));
+    }
+
+    let doc = format!(
+        " Borrowed view over serialized `{}` data that decodes fields on demand",
+        type_ident
+    );
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy)]
+        pub struct #view_ident<'a> {
+            data: &'a [u8],
+        }
+
+        impl<'a> #view_ident<'a> {
+            /// Wraps serialized struct data, without the account discriminator
+            pub fn new(data: &'a [u8]) -> Self {
+                Self { data }
+            }
+
+            /// Wraps account data after checking that it starts with the account's discriminator
+            pub fn from_account_data(data: &'a [u8]) -> Result<Self, #account_decode_error> {
+                match data.strip_prefix(&#type_ident::DISCRIMINATOR[..]) {
+                    Some(payload) => Ok(Self::new(payload)),
+                    None => Err(#account_decode_error::UnknownDiscriminator),
+                }
+            }
+
+            /// Decodes every field
+            pub fn decode(&self) -> ::std::io::Result<#type_ident> {
+                <#type_ident as ::borsh::BorshDeserialize>::deserialize(&mut &self.data[..])
+            }
+
+            #( #accessors )*
+
+            #( #offset_fns )*
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn accessors(fields: Value) -> Vec<String> {
+        let ident = |name| syn::Ident::new(name, proc_macro2::Span::call_site());
+        let fields = fields.as_array().unwrap();
+        let view = account_view(
+            &ident("Pool"),
+            &ident("PoolView"),
+            &ident("AccountDecodeError"),
+            fields,
+            &HashMap::new(),
+        );
+        let view: syn::File = syn::parse2(view).unwrap();
+        let syn::Item::Impl(view_impl) = &view.items[1] else {
+            panic!("expected the view's impl");
+        };
+        view_impl
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                syn::ImplItem::Fn(f) if matches!(f.vis, syn::Visibility::Public(_)) => {
+                    Some(f.sig.ident.to_string())
+                }
+                _ => None,
+            })
+            .filter(|name| !["new", "from_account_data", "decode"].contains(&name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn fields_after_an_unreadable_field_get_no_accessor() {
+        assert_eq!(
+            accessors(json!([
+                { "name": "authority", "type": "pubkey" },
+                { "name": "name", "type": "string" },
+                { "name": "bump", "type": "u8" },
+            ])),
+            ["authority", "name", "bump"]
+        );
+        assert_eq!(
+            accessors(json!([{ "name": "authority" }, { "name": "bump", "type": "u8" }])),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            accessors(json!([
+                { "name": "authority", "type": "pubkey" },
+                { "type": "string" },
+                { "name": "bump", "type": "u8" },
+            ])),
+            ["authority"]
+        );
+    }
+}
@@ -0,0 +1,24 @@
+use solana_sdk::pubkey::Pubkey;
+
+/// Static metadata of an instruction account, from the IDL. Generated `<Instruction>Accounts`
+/// structs list one per account, in order.
+///
+/// Composite account groups are flattened: each account in a group gets its own entry, named
+/// `<group>.<account>`, so entries line up with the instruction's keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdlAccountMeta {
+    /// IDL name of the account, prefixed with its group's name for accounts in composite groups
+    pub name: &'static str,
+    /// Whether the account must sign the transaction
+    pub is_signer: bool,
+    /// Whether the instruction may modify the account
+    pub is_writable: bool,
+    /// Whether the account may be left out, in which case Anchor expects the program ID in its
+    /// place
+    pub is_optional: bool,
+    /// Address the account must have, e.g. for programs and sysvars
+    pub fixed_address: Option<Pubkey>,
+    /// Names of other accounts in the instruction that store this account's address in a field
+    /// of the same name (Anchor `has_one` constraints)
+    pub relations: &'static [&'static str],
+}
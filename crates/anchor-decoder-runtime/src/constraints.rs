@@ -0,0 +1,148 @@
+use std::fmt;
+
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::IdlAccountMeta;
+
+/// An IDL account constraint that an instruction's accounts don't satisfy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstraintViolation {
+    /// The instruction has fewer accounts than the IDL lists
+    MissingAccount { name: &'static str },
+    /// An account with a fixed address in the IDL has a different key
+    AddressMismatch {
+        name: &'static str,
+        expected: Pubkey,
+        actual: Pubkey,
+    },
+    /// An account the IDL marks as a signer isn't signed in the message
+    MissingSigner { name: &'static str },
+    /// An account the IDL marks as writable is read-only in the message
+    NotWritable { name: &'static str },
+    /// A PDA account's key isn't the address derived from its seeds
+    PdaMismatch {
+        name: &'static str,
+        expected: Pubkey,
+        actual: Pubkey,
+    },
+    /// A related account's data stores a different address for the account than its key
+    RelationMismatch {
+        name: &'static str,
+        related: &'static str,
+        stored: Pubkey,
+        actual: Pubkey,
+    },
+}
+
+impl fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstraintViolation::MissingAccount { name } => {
+                write!(f, "account `{}` is missing", name)
+            }
+            ConstraintViolation::AddressMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "account `{}` must be {}, found {}",
+                name, expected, actual
+            ),
+            ConstraintViolation::MissingSigner { name } => {
+                write!(f, "account `{}` must be a signer", name)
+            }
+            ConstraintViolation::NotWritable { name } => {
+                write!(f, "account `{}` must be writable", name)
+            }
+            ConstraintViolation::PdaMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "account `{}` must be the PDA {}, found {}",
+                name, expected, actual
+            ),
+            ConstraintViolation::RelationMismatch {
+                name,
+                related,
+                stored,
+                actual,
+            } => write!(
+                f,
+                "account `{}` is {}, but `{}` stores {}",
+                name, actual, related, stored
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConstraintViolation {}
+
+/// Checks an instruction's accounts against the IDL constraints that can be checked offline,
+/// returning every violation found. Used by the generated `DecodedInstruction::validate`.
+///
+/// `accounts` are the instruction's accounts with the signer and writable flags from the message,
+/// in order. Optional accounts passed as `program_id` are treated as omitted and not checked.
+/// `stored_address` returns the address a related account stores in the field with the given
+/// name, given the related account's key, or `None` if its data isn't available; relations
+/// without data are skipped. Accounts in composite groups are stored under their own name, without
+/// the `<group>.` prefix.
+pub fn check_account_constraints(
+    program_id: &Pubkey,
+    metas: &[IdlAccountMeta],
+    accounts: &[AccountMeta],
+    stored_address: impl Fn(&Pubkey, &str) -> Option<Pubkey>,
+) -> Vec<ConstraintViolation> {
+    let mut violations = Vec::new();
+    for (index, meta) in metas.iter().enumerate() {
+        let Some(account) = accounts.get(index) else {
+            violations.push(ConstraintViolation::MissingAccount { name: meta.name });
+            continue;
+        };
+        if meta.is_optional && account.pubkey == *program_id {
+            continue;
+        }
+
+        if let Some(expected) = meta.fixed_address {
+            if account.pubkey != expected {
+                violations.push(ConstraintViolation::AddressMismatch {
+                    name: meta.name,
+                    expected,
+                    actual: account.pubkey,
+                });
+            }
+        }
+        if meta.is_signer && !account.is_signer {
+            violations.push(ConstraintViolation::MissingSigner { name: meta.name });
+        }
+        if meta.is_writable && !account.is_writable {
+            violations.push(ConstraintViolation::NotWritable { name: meta.name });
+        }
+
+        for related in meta.relations {
+            let Some(related_account) = metas
+                .iter()
+                .position(|m| m.name == *related)
+                .and_then(|related_index| accounts.get(related_index))
+            else {
+                continue;
+            };
+            let field = meta.name.rsplit('.').next().unwrap_or(meta.name);
+            let Some(stored) = stored_address(&related_account.pubkey, field) else {
+                continue;
+            };
+            if stored != account.pubkey {
+                violations.push(ConstraintViolation::RelationMismatch {
+                    name: meta.name,
+                    related,
+                    stored,
+                    actual: account.pubkey,
+                });
+            }
+        }
+    }
+    violations
+}
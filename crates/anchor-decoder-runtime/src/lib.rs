@@ -0,0 +1,15 @@
+//! Runtime support for decoders generated by the `anchor_idl` macro.
+//!
+//! Every `anchor_idl` expansion implements [`ProgramDecoder`] on a generated `Decoder` unit
+//! struct, so decoders for several programs can be routed by program ID through a
+//! [`DecoderRegistry`].
+
+mod account_meta;
+mod constraints;
+mod registry;
+mod summary;
+
+pub use account_meta::IdlAccountMeta;
+pub use constraints::{check_account_constraints, ConstraintViolation};
+pub use registry::{Decoded, DecodedValue, DecoderRegistry, ProgramDecoder};
+pub use summary::{write_instruction_accounts, write_pubkey, Summarize, Verbosity};
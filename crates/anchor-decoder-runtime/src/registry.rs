@@ -0,0 +1,189 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use solana_sdk::pubkey::Pubkey;
+
+/// Decoder for a single program, implemented by every `anchor_idl` expansion.
+pub trait ProgramDecoder: Send + Sync {
+    type Instruction: Debug + Send + Sync + 'static;
+    type Account: Debug + Send + Sync + 'static;
+    type Event: Debug + Send + Sync + 'static;
+
+    /// Address of the program this decoder handles
+    fn program_id(&self) -> Pubkey;
+
+    /// Program name from the IDL metadata
+    fn program_name(&self) -> &'static str;
+
+    /// Program version from the IDL metadata
+    fn program_version(&self) -> &'static str;
+
+    /// Hash of the IDL the decoder was generated from
+    fn idl_hash(&self) -> &'static str;
+
+    fn decode_instruction(&self, data: &[u8]) -> Option<Self::Instruction>;
+
+    fn decode_account(&self, data: &[u8]) -> Option<Self::Account>;
+
+    fn decode_event(&self, data: &[u8]) -> Option<Self::Event>;
+}
+
+/// A decoded value whose concrete type depends on the program that decoded it. Blanket
+/// implemented for every `Debug + Send + Sync` type.
+pub trait DecodedValue: Any + Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any + Send + Sync>;
+}
+
+impl<T: Any + Debug + Send + Sync> DecodedValue for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any + Send + Sync> {
+        self
+    }
+}
+
+/// Result of decoding through a [`DecoderRegistry`]. The value is the program's own
+/// `DecodedInstruction`, `DecodedAccount` or `DecodedEvent`, and can be recovered with
+/// [`Decoded::downcast_ref`] or [`Decoded::downcast`].
+#[derive(Debug)]
+pub struct Decoded {
+    pub program_id: Pubkey,
+    pub program_name: &'static str,
+    pub program_version: &'static str,
+    /// Hash of the IDL the value was decoded with, to tie stored data to the exact IDL
+    pub idl_hash: &'static str,
+    pub value: Box<dyn DecodedValue>,
+}
+
+impl Decoded {
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        (*self.value).as_any().downcast_ref()
+    }
+
+    /// Takes the value out as `T`, or returns `self` unchanged if it has a different type
+    pub fn downcast<T: 'static>(self) -> Result<T, Self> {
+        if !(*self.value).as_any().is::<T>() {
+            return Err(self);
+        }
+        let value = self
+            .value
+            .into_any()
+            .downcast()
+            .expect("type was checked above");
+        Ok(*value)
+    }
+}
+
+/// Object-safe view of a [`ProgramDecoder`] with its associated types erased.
+trait ErasedDecoder: Send + Sync {
+    fn program_name(&self) -> &'static str;
+
+    fn program_version(&self) -> &'static str;
+
+    fn idl_hash(&self) -> &'static str;
+
+    fn decode_instruction(&self, data: &[u8]) -> Option<Box<dyn DecodedValue>>;
+
+    fn decode_account(&self, data: &[u8]) -> Option<Box<dyn DecodedValue>>;
+
+    fn decode_event(&self, data: &[u8]) -> Option<Box<dyn DecodedValue>>;
+}
+
+impl<D: ProgramDecoder> ErasedDecoder for D {
+    fn program_name(&self) -> &'static str {
+        ProgramDecoder::program_name(self)
+    }
+
+    fn program_version(&self) -> &'static str {
+        ProgramDecoder::program_version(self)
+    }
+
+    fn idl_hash(&self) -> &'static str {
+        ProgramDecoder::idl_hash(self)
+    }
+
+    fn decode_instruction(&self, data: &[u8]) -> Option<Box<dyn DecodedValue>> {
+        ProgramDecoder::decode_instruction(self, data).map(|v| Box::new(v) as Box<dyn DecodedValue>)
+    }
+
+    fn decode_account(&self, data: &[u8]) -> Option<Box<dyn DecodedValue>> {
+        ProgramDecoder::decode_account(self, data).map(|v| Box::new(v) as Box<dyn DecodedValue>)
+    }
+
+    fn decode_event(&self, data: &[u8]) -> Option<Box<dyn DecodedValue>> {
+        ProgramDecoder::decode_event(self, data).map(|v| Box::new(v) as Box<dyn DecodedValue>)
+    }
+}
+
+/// Routes decoding to the right program decoder by program ID.
+///
+/// ```ignore
+/// let mut registry = DecoderRegistry::new();
+/// registry.register(whirlpools_decoder::Decoder);
+/// registry.register(jupiter_decoder::Decoder);
+///
+/// if let Some(decoded) = registry.decode_instruction(&program_id, &data) {
+///     println!("{}: {:?}", decoded.program_name, decoded.value);
+/// }
+/// ```
+#[derive(Default)]
+pub struct DecoderRegistry {
+    decoders: HashMap<Pubkey, Box<dyn ErasedDecoder>>,
+}
+
+impl DecoderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a decoder under its program ID, replacing any decoder already registered for
+    /// that program
+    pub fn register<D: ProgramDecoder + 'static>(&mut self, decoder: D) -> &mut Self {
+        self.decoders
+            .insert(decoder.program_id(), Box::new(decoder));
+        self
+    }
+
+    pub fn contains(&self, program_id: &Pubkey) -> bool {
+        self.decoders.contains_key(program_id)
+    }
+
+    pub fn program_ids(&self) -> impl Iterator<Item = &Pubkey> {
+        self.decoders.keys()
+    }
+
+    /// Decodes instruction data for the program it was sent to
+    pub fn decode_instruction(&self, program_id: &Pubkey, data: &[u8]) -> Option<Decoded> {
+        self.decode(program_id, |decoder| decoder.decode_instruction(data))
+    }
+
+    /// Decodes account data for the program that owns the account
+    pub fn decode_account(&self, owner: &Pubkey, data: &[u8]) -> Option<Decoded> {
+        self.decode(owner, |decoder| decoder.decode_account(data))
+    }
+
+    /// Decodes event data for the program that emitted it
+    pub fn decode_event(&self, program_id: &Pubkey, data: &[u8]) -> Option<Decoded> {
+        self.decode(program_id, |decoder| decoder.decode_event(data))
+    }
+
+    fn decode(
+        &self,
+        program_id: &Pubkey,
+        decode: impl FnOnce(&dyn ErasedDecoder) -> Option<Box<dyn DecodedValue>>,
+    ) -> Option<Decoded> {
+        let decoder = self.decoders.get(program_id)?;
+        Some(Decoded {
+            program_id: *program_id,
+            program_name: decoder.program_name(),
+            program_version: decoder.program_version(),
+            idl_hash: decoder.idl_hash(),
+            value: decode(decoder.as_ref())?,
+        })
+    }
+}
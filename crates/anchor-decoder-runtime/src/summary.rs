@@ -0,0 +1,171 @@
+use std::fmt::{self, Write};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::IdlAccountMeta;
+
+/// How much detail summaries include
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Verbosity {
+    /// Top-level fields only: nested structs and enum fields are elided as `{..}`, lists as
+    /// their length, and instruction accounts are left out
+    Brief,
+    /// Nested values in full, lists up to 8 items, and the signer and writable accounts of
+    /// instructions
+    #[default]
+    Normal,
+    /// Everything, including every instruction account and full addresses
+    Full,
+}
+
+/// Number of list items shown at `Verbosity::Normal`
+const NORMAL_LIST_ITEMS: usize = 8;
+
+/// One-line rendering of a decoded value, with IDL names and abbreviated addresses. Implemented
+/// for the primitive types IDL fields map to, and generated for IDL types and the decoded enums.
+pub trait Summarize {
+    /// Writes the value as it appears in a summary
+    fn summarize(&self, out: &mut dyn Write, verbosity: Verbosity) -> fmt::Result;
+
+    /// Returns the summary as a string
+    fn summary(&self, verbosity: Verbosity) -> String {
+        let mut out = String::new();
+        // Writing to a String never fails.
+        let _ = self.summarize(&mut out, verbosity);
+        out
+    }
+}
+
+/// Writes an address, abbreviated to its first 4 characters below `Verbosity::Full`
+pub fn write_pubkey(out: &mut dyn Write, pubkey: &Pubkey, verbosity: Verbosity) -> fmt::Result {
+    let address = pubkey.to_string();
+    if verbosity == Verbosity::Full {
+        out.write_str(&address)
+    } else {
+        write!(out, "{}…", &address[..4])
+    }
+}
+
+/// Writes an instruction's accounts as ` name=address` pairs: none at `Verbosity::Brief`, the
+/// signers and writable accounts at `Verbosity::Normal`, and all of them at `Verbosity::Full`.
+/// Optional accounts passed as `program_id` and accounts past the IDL's are left out. Used by the
+/// generated `DecodedTransactionInstruction` summary.
+pub fn write_instruction_accounts(
+    out: &mut dyn Write,
+    program_id: &Pubkey,
+    metas: &[IdlAccountMeta],
+    accounts: &[Pubkey],
+    verbosity: Verbosity,
+) -> fmt::Result {
+    if verbosity == Verbosity::Brief {
+        return Ok(());
+    }
+    for (meta, account) in metas.iter().zip(accounts) {
+        if meta.is_optional && account == program_id {
+            continue;
+        }
+        if verbosity == Verbosity::Full || meta.is_signer || meta.is_writable {
+            write!(out, " {}=", meta.name)?;
+            write_pubkey(out, account, verbosity)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes an integer with `_` between groups of three digits, e.g. `1_000_000`
+fn write_grouped(out: &mut dyn Write, digits: &str) -> fmt::Result {
+    let (sign, digits) = match digits.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", digits),
+    };
+    out.write_str(sign)?;
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.write_char('_')?;
+        }
+        out.write_char(digit)?;
+    }
+    Ok(())
+}
+
+macro_rules! summarize_integers {
+    ($($ty:ty),*) => {
+        $(
+            impl Summarize for $ty {
+                fn summarize(&self, out: &mut dyn Write, _: Verbosity) -> fmt::Result {
+                    write_grouped(out, &self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+summarize_integers!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+macro_rules! summarize_display {
+    ($($ty:ty),*) => {
+        $(
+            impl Summarize for $ty {
+                fn summarize(&self, out: &mut dyn Write, _: Verbosity) -> fmt::Result {
+                    write!(out, "{}", self)
+                }
+            }
+        )*
+    };
+}
+
+summarize_display!(f32, f64, bool);
+
+impl Summarize for String {
+    fn summarize(&self, out: &mut dyn Write, _: Verbosity) -> fmt::Result {
+        write!(out, "{:?}", self)
+    }
+}
+
+impl Summarize for Pubkey {
+    fn summarize(&self, out: &mut dyn Write, verbosity: Verbosity) -> fmt::Result {
+        write_pubkey(out, self, verbosity)
+    }
+}
+
+impl<T: Summarize> Summarize for Option<T> {
+    fn summarize(&self, out: &mut dyn Write, verbosity: Verbosity) -> fmt::Result {
+        match self {
+            Some(value) => value.summarize(out, verbosity),
+            None => out.write_str("None"),
+        }
+    }
+}
+
+impl<T: Summarize> Summarize for [T] {
+    fn summarize(&self, out: &mut dyn Write, verbosity: Verbosity) -> fmt::Result {
+        let shown = match verbosity {
+            Verbosity::Brief => return write!(out, "[..; {}]", self.len()),
+            Verbosity::Normal => NORMAL_LIST_ITEMS,
+            Verbosity::Full => self.len(),
+        };
+        out.write_char('[')?;
+        for (i, item) in self.iter().take(shown).enumerate() {
+            if i > 0 {
+                out.write_str(", ")?;
+            }
+            item.summarize(out, verbosity)?;
+        }
+        if self.len() > shown {
+            write!(out, ", … {} more", self.len() - shown)?;
+        }
+        out.write_char(']')
+    }
+}
+
+impl<T: Summarize> Summarize for Vec<T> {
+    fn summarize(&self, out: &mut dyn Write, verbosity: Verbosity) -> fmt::Result {
+        self.as_slice().summarize(out, verbosity)
+    }
+}
+
+impl<T: Summarize, const N: usize> Summarize for [T; N] {
+    fn summarize(&self, out: &mut dyn Write, verbosity: Verbosity) -> fmt::Result {
+        self.as_slice().summarize(out, verbosity)
+    }
+}
@@ -1,4 +1,4 @@
 use anchor_decoder::anchor_idl;
 
 #[anchor_idl("./idl.json")]
-pub const ID: Pubkey = crate::ID;
+pub const ID: solana_sdk::pubkey::Pubkey = IDL_ADDRESS;
@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+/// Writes decoders with `generate_to_file`, as a decoder crate's build script would, and a file
+/// declaring them as modules for `src/lib.rs` to include
+fn main() {
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let mut modules = String::new();
+    for (module, idl) in [
+        ("pumpdotfun_file", "../examples/pumpdotfun/idl.json"),
+        ("whirlpools_file", "../examples/whirlpools/idl.json"),
+    ] {
+        println!("cargo:rerun-if-changed={}", idl);
+        let out_path = out_dir.join(format!("{}.rs", module));
+        anchor_decoder_codegen::generate_to_file(idl, &out_path).unwrap();
+        modules += &format!("#[path = {:?}]\npub mod {};\n", out_path, module);
+    }
+    std::fs::write(out_dir.join("modules.rs"), modules).unwrap();
+}
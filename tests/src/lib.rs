@@ -0,0 +1,44 @@
+//! Decoders generated from the example IDLs with non-default options, for the tests in
+//! `tests/`. Warnings are denied so generated code that doesn't lint clean fails the build.
+#![deny(warnings)]
+
+use anchor_decoder::anchor_idl;
+
+/// Every generated data type implements `Default`, including enums and structs with arrays
+/// longer than 32
+#[anchor_idl("../examples/whirlpools/idl.json", derive(Default, Clone, PartialEq))]
+pub mod whirlpools_default {}
+
+#[anchor_idl("../examples/tensor-amm/idl.json", types(derive(Default)))]
+pub mod tensor_amm_default {}
+
+// Decoders written to files by the build script, compiled with warnings denied like the rest
+// of the crate
+include!(concat!(env!("OUT_DIR"), "/modules.rs"));
+
+/// The generated code's support functions don't clash with the module's own items
+#[anchor_idl("../examples/whirlpools/idl.json")]
+pub mod whirlpools_helper_names {
+    pub fn decode_compiled_instruction() {}
+    pub fn read_discriminator() {}
+    pub fn view_field() {}
+    pub fn skip_field() {}
+    pub fn check_account_owner() {}
+    pub fn try_decode_account() {}
+}
+
+/// Accounts named like the instruction builder's own bindings
+#[anchor_idl("idls/builder_names.json")]
+pub mod builder_names {}
+
+/// IDL types named like the items generated for every program
+#[anchor_idl("idls/reserved_names.json")]
+pub mod reserved_names {}
+
+#[anchor_idl(versions(
+    v1("../examples/whirlpools/idl.json"),
+    v2("../examples/whirlpools/idl.json", from_slot = 1_000),
+))]
+pub mod whirlpools_versions {
+    pub fn version_index() {}
+}
@@ -0,0 +1,52 @@
+use anchor_decoder_tests::whirlpools_default::{
+    self as whirlpools, decode_account, AccountFilter, DecodedAccount, Whirlpool,
+};
+
+/// Account data as allocated with `extra` bytes of unused space after the account
+fn allocated(whirlpool: &Whirlpool, extra: usize) -> Vec<u8> {
+    let mut data = Whirlpool::DISCRIMINATOR.to_vec();
+    data.extend(borsh::BorshSerialize::try_to_vec(whirlpool).unwrap());
+    data.resize(data.len() + extra, 0);
+    data
+}
+
+#[test]
+fn trailing_bytes_are_ignored() {
+    let whirlpool = Whirlpool {
+        liquidity: 1_000,
+        tick_spacing: 64,
+        ..Default::default()
+    };
+    let data = allocated(&whirlpool, 100);
+    assert_eq!(data.len(), Whirlpool::SPACE + 100);
+
+    match decode_account(&data) {
+        Some(DecodedAccount::Whirlpool(decoded)) => assert_eq!(decoded, whirlpool),
+        other => panic!("expected a Whirlpool, got {:?}", other),
+    }
+    let decoded = Whirlpool::decode_account_info(&whirlpools::ID, &data).unwrap();
+    assert_eq!(decoded, whirlpool);
+}
+
+#[test]
+fn filters_match_over_allocated_accounts() {
+    let data = allocated(&Whirlpool::default(), 100);
+    for filter in Whirlpool::filters() {
+        match filter {
+            AccountFilter::Memcmp { offset, bytes } => {
+                assert_eq!(&data[offset..offset + bytes.len()], &bytes[..])
+            }
+            AccountFilter::DataSize(size) => panic!("unexpected size filter {}", size),
+        }
+    }
+    assert!(matches!(
+        Whirlpool::data_size_filter(),
+        AccountFilter::DataSize(size) if size == Whirlpool::SPACE as u64
+    ));
+}
+
+#[test]
+fn truncated_accounts_fail() {
+    let data = allocated(&Whirlpool::default(), 0);
+    assert!(decode_account(&data[..data.len() - 1]).is_none());
+}
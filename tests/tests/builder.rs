@@ -0,0 +1,25 @@
+use anchor_decoder_tests::builder_names::{Deposit, DepositAccounts, DepositKeys, ID};
+use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey, system_program};
+
+#[test]
+fn accounts_named_like_builder_bindings() {
+    let keys = DepositKeys {
+        data: Pubkey::new_unique(),
+        keys: Pubkey::new_unique(),
+    };
+    let ix = Deposit { amount: 7 }.instruction(&keys);
+
+    let (vault, _) = DepositAccounts::find_vault_address(&keys.data, &keys.keys);
+    assert_eq!(ix.program_id, ID);
+    assert_eq!(
+        ix.accounts,
+        [
+            AccountMeta::new(keys.data, true),
+            AccountMeta::new(keys.keys, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ]
+    );
+    assert_eq!(&ix.data[..8], Deposit::DISCRIMINATOR);
+    assert_eq!(ix.data[8..], 7u64.to_le_bytes());
+}
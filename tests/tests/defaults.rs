@@ -0,0 +1,31 @@
+use anchor_decoder_tests::{tensor_amm_default, whirlpools_default};
+
+#[test]
+fn enums_default_to_their_first_variant() {
+    assert_eq!(
+        whirlpools_default::AccountsType::default(),
+        whirlpools_default::AccountsType::TransferHookA
+    );
+    assert!(matches!(
+        tensor_amm_default::PayloadTypeLocal::default(),
+        tensor_amm_default::PayloadTypeLocal::Pubkey(key) if key == Default::default()
+    ));
+}
+
+#[test]
+fn long_arrays_default_element_by_element() {
+    let tick_array = whirlpools_default::TickArray::default();
+    assert_eq!(tick_array.ticks.len(), 88);
+    assert_eq!(tick_array.ticks[87], whirlpools_default::Tick::default());
+}
+
+#[test]
+fn default_round_trips_through_borsh() {
+    let whirlpool = whirlpools_default::Whirlpool::default();
+    let data = borsh::BorshSerialize::try_to_vec(&whirlpool).unwrap();
+    assert_eq!(data.len(), whirlpools_default::Whirlpool::LEN);
+    assert_eq!(
+        whirlpools_default::Whirlpool::decode(&data).unwrap(),
+        whirlpool
+    );
+}
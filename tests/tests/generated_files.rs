@@ -0,0 +1,36 @@
+use anchor_decoder_tests::{pumpdotfun_file, whirlpools_file};
+
+#[test]
+fn generated_file_matches_macro() {
+    assert_eq!(pumpdotfun_file::ID, pumpdotfun_decoder::ID);
+    assert_eq!(
+        pumpdotfun_file::Global::DISCRIMINATOR,
+        pumpdotfun_decoder::Global::DISCRIMINATOR
+    );
+    assert_eq!(
+        pumpdotfun_file::INSTRUCTION_DISCRIMINATORS,
+        pumpdotfun_decoder::INSTRUCTION_DISCRIMINATORS
+    );
+
+    let mut data = pumpdotfun_file::Global::DISCRIMINATOR.to_vec();
+    data.extend([1u8; 8 + 32 * 2 + 8 * 5]);
+    let from_file = match pumpdotfun_file::decode_account(&data) {
+        Some(pumpdotfun_file::DecodedAccount::Global(global)) => global,
+        other => panic!("expected Global, got {:?}", other),
+    };
+    let from_macro = match pumpdotfun_decoder::decode_account(&data) {
+        Some(pumpdotfun_decoder::DecodedAccount::Global(global)) => global,
+        other => panic!("expected Global, got {:?}", other),
+    };
+    assert_eq!(format!("{:?}", from_file), format!("{:?}", from_macro));
+}
+
+#[test]
+fn generated_file_decodes() {
+    let mut data = whirlpools_file::Whirlpool::DISCRIMINATOR.to_vec();
+    data.resize(whirlpools_file::Whirlpool::SPACE, 0);
+    assert!(matches!(
+        whirlpools_file::decode_account(&data),
+        Some(whirlpools_file::DecodedAccount::Whirlpool(_))
+    ));
+}
@@ -0,0 +1,37 @@
+use pumpdotfun_decoder::{AccountFilter, Global, GlobalView};
+use solana_sdk::pubkey::Pubkey;
+
+fn global() -> Global {
+    Global {
+        initialized: true,
+        authority: Pubkey::new_unique(),
+        feeRecipient: Pubkey::new_unique(),
+        initialVirtualTokenReserves: 1,
+        initialVirtualSolReserves: 2,
+        initialRealTokenReserves: 3,
+        tokenTotalSupply: 4,
+        feeBasisPoints: 5,
+    }
+}
+
+#[test]
+fn camel_case_fields_get_snake_case_methods() {
+    let global = global();
+    let mut data = Global::DISCRIMINATOR.to_vec();
+    data.extend(borsh::BorshSerialize::try_to_vec(&global).unwrap());
+
+    let view = GlobalView::from_account_data(&data).unwrap();
+    assert_eq!(view.fee_recipient().unwrap(), global.feeRecipient);
+    assert_eq!(view.initial_virtual_sol_reserves().unwrap(), 2);
+
+    let AccountFilter::Memcmp { offset, bytes } =
+        Global::filter_by_fee_recipient(&global.feeRecipient)
+    else {
+        panic!("expected a memcmp filter");
+    };
+    assert_eq!(
+        offset,
+        Global::DISCRIMINATOR.len() + Global::FEE_RECIPIENT_OFFSET
+    );
+    assert_eq!(&data[offset..offset + 32], &bytes[..]);
+}
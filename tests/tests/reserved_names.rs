@@ -0,0 +1,47 @@
+use anchor_decoder_runtime::ProgramDecoder;
+use anchor_decoder_tests::reserved_names::{
+    decode_account, decode_event, decode_instruction, AccountFilter, AnchorAccountFilter,
+    AnchorDecodedEvent, AnchorDecoder, Configure, DecodedAccount, DecodedEvent, DecodedInstruction,
+    Decoder,
+};
+use borsh::BorshSerialize;
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn types_keep_their_names_and_generated_items_fall_back() {
+    let account = AccountFilter {
+        owner: Pubkey::new_unique(),
+        decoder: Decoder { depth: 3 },
+    };
+    let mut data = AccountFilter::DISCRIMINATOR.to_vec();
+    data.extend(account.try_to_vec().unwrap());
+    let Some(DecodedAccount::AccountFilter(decoded)) = decode_account(&data) else {
+        panic!("expected an AccountFilter account");
+    };
+    assert_eq!(decoded.owner, account.owner);
+    assert!(matches!(
+        AccountFilter::discriminator_filter(),
+        AnchorAccountFilter::Memcmp { offset: 0, .. }
+    ));
+
+    let event = DecodedEvent { depth: 7 };
+    let mut data = vec![3];
+    data.extend(event.try_to_vec().unwrap());
+    assert!(matches!(
+        decode_event(&data),
+        Some(AnchorDecodedEvent::DecodedEvent(DecodedEvent { depth: 7 }))
+    ));
+
+    let ix = Configure {
+        decoder: Decoder { depth: 1 },
+    };
+    let mut data = Configure::DISCRIMINATOR.to_vec();
+    data.extend(ix.try_to_vec().unwrap());
+    assert!(matches!(
+        decode_instruction(&data),
+        Some(DecodedInstruction::Configure(Configure {
+            decoder: Decoder { depth: 1 }
+        }))
+    ));
+    assert!(AnchorDecoder.decode_instruction(&data).is_some());
+}
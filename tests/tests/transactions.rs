@@ -0,0 +1,87 @@
+use std::borrow::Cow;
+
+use pumpdotfun_decoder::{
+    decode_transaction, load_lookup_table_addresses, resolve_account_keys, Buy, BuyKeys,
+    DecodedInstruction,
+};
+use solana_sdk::address_lookup_table::state::{AddressLookupTable, LookupTableMeta};
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+
+#[test]
+fn lookup_table_accounts_resolve_writable_before_readonly() {
+    let keys = BuyKeys {
+        fee_recipient: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        associated_bonding_curve: Pubkey::new_unique(),
+        associated_user: Pubkey::new_unique(),
+        user: Pubkey::new_unique(),
+    };
+    let ix = Buy {
+        amount: 1_000,
+        maxSolCost: 2_000,
+    }
+    .instruction(&keys);
+
+    // The readonly mint comes first in the table, so resolving in table order would misplace it.
+    let table_key = Pubkey::new_unique();
+    let addresses = vec![keys.mint, keys.associated_bonding_curve, keys.fee_recipient];
+    let table_data = AddressLookupTable {
+        meta: LookupTableMeta::default(),
+        addresses: Cow::Borrowed(&addresses),
+    }
+    .serialize_for_tests()
+    .unwrap();
+    let table = AddressLookupTableAccount {
+        key: table_key,
+        addresses: addresses.clone(),
+    };
+    let message = VersionedMessage::V0(
+        v0::Message::try_compile(
+            &keys.user,
+            std::slice::from_ref(&ix),
+            &[table],
+            Hash::default(),
+        )
+        .unwrap(),
+    );
+    let lookups = message.address_table_lookups().unwrap();
+    assert_eq!(lookups.len(), 1);
+    assert_eq!(lookups[0].readonly_indexes, [0]);
+
+    let loaded = load_lookup_table_addresses(&message, &[(table_key, &table_data)]).unwrap();
+    assert_eq!(loaded.readonly, [keys.mint]);
+    let mut writable = loaded.writable.clone();
+    writable.sort();
+    let mut expected = vec![keys.associated_bonding_curve, keys.fee_recipient];
+    expected.sort();
+    assert_eq!(writable, expected);
+
+    let account_keys = resolve_account_keys(&message, Some(&loaded));
+    let static_keys = message.static_account_keys();
+    assert_eq!(&account_keys[..static_keys.len()], static_keys);
+    assert_eq!(
+        account_keys[static_keys.len()..],
+        [loaded.writable.clone(), loaded.readonly.clone()].concat()
+    );
+
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default()],
+        message,
+    };
+    let decoded = decode_transaction(&transaction, Some(&loaded), None);
+    assert_eq!(decoded.len(), 1);
+    let ix_keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(decoded[0].accounts, ix_keys);
+    assert!(matches!(
+        decoded[0].instruction,
+        DecodedInstruction::Buy(Buy { amount: 1_000, .. })
+    ));
+
+    // Without the loaded addresses, the instruction's looked-up accounts can't be resolved.
+    assert!(decode_transaction(&transaction, None, None).is_empty());
+}
@@ -0,0 +1,142 @@
+use anchor_decoder_tests::whirlpools_default::{Whirlpool, WhirlpoolRewardInfo, WhirlpoolView};
+use borsh::BorshSerialize;
+use solana_sdk::pubkey::Pubkey;
+use tensor_marketplace_decoder::{ListState, ListStateView};
+
+/// Account data for `value`, discriminator included
+fn account_data<T: BorshSerialize>(discriminator: &[u8], value: &T) -> Vec<u8> {
+    let mut data = discriminator.to_vec();
+    data.extend(value.try_to_vec().unwrap());
+    data
+}
+
+/// Asserts that `value` is serialized at `offset` in the struct data
+fn assert_at<T: BorshSerialize>(data: &[u8], offset: usize, value: &T) {
+    let bytes = value.try_to_vec().unwrap();
+    assert_eq!(&data[offset..offset + bytes.len()], &bytes[..]);
+}
+
+#[test]
+fn fixed_size_view_matches_full_decode() {
+    let reward_info = |n: u128| WhirlpoolRewardInfo {
+        mint: Pubkey::new_unique(),
+        vault: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        emissions_per_second_x64: n,
+        growth_global_x64: n + 1,
+    };
+    let whirlpool = Whirlpool {
+        whirlpools_config: Pubkey::new_unique(),
+        whirlpool_bump: [254],
+        tick_spacing: 64,
+        tick_spacing_seed: [64, 0],
+        fee_rate: 3_000,
+        protocol_fee_rate: 300,
+        liquidity: 1 << 100,
+        sqrt_price: (1 << 64) + 5,
+        tick_current_index: -12_345,
+        protocol_fee_owed_a: 11,
+        protocol_fee_owed_b: 12,
+        token_mint_a: Pubkey::new_unique(),
+        token_vault_a: Pubkey::new_unique(),
+        fee_growth_global_a: 13,
+        token_mint_b: Pubkey::new_unique(),
+        token_vault_b: Pubkey::new_unique(),
+        fee_growth_global_b: 14,
+        reward_last_updated_timestamp: 1_700_000_000,
+        reward_infos: [reward_info(1), reward_info(2), reward_info(3)],
+    };
+    let data = account_data(&Whirlpool::DISCRIMINATOR, &whirlpool);
+    let payload = &data[Whirlpool::DISCRIMINATOR.len()..];
+    assert_eq!(payload.len(), Whirlpool::LEN);
+
+    let view = WhirlpoolView::from_account_data(&data).unwrap();
+    assert_eq!(view.decode().unwrap(), whirlpool);
+
+    macro_rules! check {
+        ($( $field:ident => $offset:ident ),* $(,)?) => {$(
+            assert_eq!(view.$field().unwrap(), whirlpool.$field, stringify!($field));
+            assert_at(payload, Whirlpool::$offset, &whirlpool.$field);
+        )*};
+    }
+    check!(
+        whirlpools_config => WHIRLPOOLS_CONFIG_OFFSET,
+        whirlpool_bump => WHIRLPOOL_BUMP_OFFSET,
+        tick_spacing => TICK_SPACING_OFFSET,
+        tick_spacing_seed => TICK_SPACING_SEED_OFFSET,
+        fee_rate => FEE_RATE_OFFSET,
+        protocol_fee_rate => PROTOCOL_FEE_RATE_OFFSET,
+        liquidity => LIQUIDITY_OFFSET,
+        sqrt_price => SQRT_PRICE_OFFSET,
+        tick_current_index => TICK_CURRENT_INDEX_OFFSET,
+        protocol_fee_owed_a => PROTOCOL_FEE_OWED_A_OFFSET,
+        protocol_fee_owed_b => PROTOCOL_FEE_OWED_B_OFFSET,
+        token_mint_a => TOKEN_MINT_A_OFFSET,
+        token_vault_a => TOKEN_VAULT_A_OFFSET,
+        fee_growth_global_a => FEE_GROWTH_GLOBAL_A_OFFSET,
+        token_mint_b => TOKEN_MINT_B_OFFSET,
+        token_vault_b => TOKEN_VAULT_B_OFFSET,
+        fee_growth_global_b => FEE_GROWTH_GLOBAL_B_OFFSET,
+        reward_last_updated_timestamp => REWARD_LAST_UPDATED_TIMESTAMP_OFFSET,
+        reward_infos => REWARD_INFOS_OFFSET,
+    );
+}
+
+#[test]
+fn view_past_variable_size_fields_matches_full_decode() {
+    let list_state = |currency: Option<Pubkey>, private_taker: Option<Pubkey>| ListState {
+        version: 1,
+        bump: [253],
+        owner: Pubkey::new_unique(),
+        asset_id: Pubkey::new_unique(),
+        amount: 5_000,
+        currency,
+        expiry: -1,
+        private_taker,
+        maker_broker: Some(Pubkey::new_unique()),
+        rent_payer: Pubkey::new_unique(),
+        cosigner: Pubkey::new_unique(),
+        reserved1: [7; 64],
+    };
+    const { assert!(!ListState::IS_FIXED_SIZE) };
+
+    // The fields after `currency` move with its length, so check both lengths.
+    for list_state in [
+        list_state(None, Some(Pubkey::new_unique())),
+        list_state(Some(Pubkey::new_unique()), None),
+    ] {
+        let data = account_data(&ListState::DISCRIMINATOR, &list_state);
+        let payload = &data[ListState::DISCRIMINATOR.len()..];
+        let view = ListStateView::from_account_data(&data).unwrap();
+        let decoded = view.decode().unwrap();
+
+        // Fields up to and including the first variable-size one have static offsets.
+        assert_at(payload, ListState::VERSION_OFFSET, &list_state.version);
+        assert_at(payload, ListState::BUMP_OFFSET, &list_state.bump);
+        assert_at(payload, ListState::OWNER_OFFSET, &list_state.owner);
+        assert_at(payload, ListState::ASSET_ID_OFFSET, &list_state.asset_id);
+        assert_at(payload, ListState::AMOUNT_OFFSET, &list_state.amount);
+        assert_at(payload, ListState::CURRENCY_OFFSET, &list_state.currency);
+
+        macro_rules! check {
+            ($( $field:ident ),* $(,)?) => {$(
+                assert_eq!(view.$field().unwrap(), list_state.$field, stringify!($field));
+                assert_eq!(decoded.$field, list_state.$field, stringify!($field));
+            )*};
+        }
+        check!(
+            version,
+            bump,
+            owner,
+            asset_id,
+            amount,
+            currency,
+            expiry,
+            private_taker,
+            maker_broker,
+            rent_payer,
+            cosigner,
+            reserved1,
+        );
+    }
+}